@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::IoContext;
+use crate::{OcrDocument, OcrError, Result, create_parent_dir};
+
+/// A JSON Schema the API fills in while reading the document, for structured extraction
+/// such as invoice fields.
+///
+/// Set as [`OcrOptions::document_annotation_format`](crate::OcrOptions::document_annotation_format)
+/// to annotate the document as a whole, or as
+/// [`OcrOptions::bbox_annotation_format`](crate::OcrOptions::bbox_annotation_format) to
+/// annotate each extracted image.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationSchema {
+    /// Name of the schema, as sent to the API.
+    pub name: String,
+    pub schema: Value,
+}
+
+impl AnnotationSchema {
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+        }
+    }
+
+    /// Read a JSON Schema file. The schema is named after the file, e.g. `invoice` for
+    /// `invoice.json`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json =
+            fs::read_to_string(path).io_context(|| format!("Failed to read {}", path.display()))?;
+        let schema = serde_json::from_str(&json)
+            .map_err(|e| OcrError::decode(format!("Invalid JSON Schema {}", path.display()), e))?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "schema".to_string());
+        Ok(Self::new(name, schema))
+    }
+
+    /// The `*_annotation_format` request field for this schema.
+    pub(crate) fn response_format(&self) -> Value {
+        serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": self.name,
+                "schema": self.schema,
+                "strict": true,
+            },
+        })
+    }
+}
+
+/// Decode an annotation as returned by the API, a JSON document in a string. Anything
+/// that is not valid JSON is kept as a plain string.
+pub(crate) fn parse_annotation(annotation: String) -> Value {
+    serde_json::from_str(&annotation).unwrap_or(Value::String(annotation))
+}
+
+/// Contents of the annotations sidecar, see [`write_annotations`].
+#[derive(Serialize)]
+struct Annotations<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_annotation: Option<&'a Value>,
+    images: Vec<ImageAnnotation<'a>>,
+}
+
+#[derive(Serialize)]
+struct ImageAnnotation<'a> {
+    /// Zero-based page index, as in [`OcrResponse`](crate::OcrResponse) pages.
+    page_index: u32,
+    id: &'a str,
+    annotation: &'a Value,
+}
+
+/// Sidecar file that [`write_annotations`] writes for `output_path`, e.g.
+/// `report.annotations.json` for `report.md`.
+pub fn annotations_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("annotations.json")
+}
+
+/// Write the document and image annotations of `document` as JSON next to
+/// `output_path`, see [`annotations_path`]. Returns whether there were any to write.
+///
+/// The file holds `document_annotation` (the filled-in document schema, if requested)
+/// and `images`, one entry per annotated image with its `page_index`, `id` and
+/// `annotation`.
+pub fn write_annotations(output_path: &Path, document: &OcrDocument) -> Result<bool> {
+    let images: Vec<_> = document
+        .pages
+        .iter()
+        .flat_map(|page| {
+            page.images.iter().filter_map(|img| {
+                Some(ImageAnnotation {
+                    page_index: page.index,
+                    id: &img.id,
+                    annotation: img.annotation.as_ref()?,
+                })
+            })
+        })
+        .collect();
+    if document.document_annotation.is_none() && images.is_empty() {
+        return Ok(false);
+    }
+    let annotations = Annotations {
+        document_annotation: document.document_annotation.as_ref(),
+        images,
+    };
+    let path = annotations_path(output_path);
+    create_parent_dir(&path)?;
+    let json = serde_json::to_string_pretty(&annotations)
+        .map_err(|e| OcrError::decode("Failed to serialize annotations", e))?;
+    fs::write(&path, json).io_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_files_are_named_after_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invoice.json");
+        fs::write(
+            &path,
+            r#"{"type": "object", "properties": {"total": {"type": "number"}}}"#,
+        )
+        .unwrap();
+        let schema = AnnotationSchema::load(&path).unwrap();
+        assert_eq!(schema.name, "invoice");
+        assert_eq!(
+            schema.response_format()["json_schema"]["schema"]["properties"]["total"]["type"],
+            "number"
+        );
+        assert_eq!(schema.response_format()["type"], "json_schema");
+    }
+
+    #[test]
+    fn annotations_are_json_or_kept_as_text() {
+        assert_eq!(
+            parse_annotation(r#"{"total": 12.5}"#.to_string()),
+            serde_json::json!({"total": 12.5})
+        );
+        assert_eq!(
+            parse_annotation("not json".to_string()),
+            Value::String("not json".to_string())
+        );
+    }
+}
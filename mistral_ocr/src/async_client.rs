@@ -0,0 +1,318 @@
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::client::{
+    ClientConfig, RequestPart, content_type, downloaded_input, is_transient_error,
+    is_transient_status, log_done, retry_after, source_info, status_error,
+};
+use crate::files::FilePurpose;
+use crate::{
+    OcrClient, OcrClientBuilder, OcrDocument, OcrInput, OcrOptions, OcrRequest, OcrResponse,
+    Result, UploadMode, write_output,
+};
+
+/// Async counterpart of [`OcrClient`] for use inside a Tokio runtime.
+///
+/// Built with [`OcrClientBuilder::build_async`]; shares request building, retries and
+/// rendering with the blocking client. Office document conversion and file output
+/// still run synchronously on the calling task.
+#[derive(Clone, Debug)]
+pub struct AsyncOcrClient {
+    pub(crate) http: reqwest::Client,
+    pub(crate) config: ClientConfig,
+}
+
+impl AsyncOcrClient {
+    /// Client with default settings for the public Mistral API.
+    pub fn new(api_key: impl Into<String>) -> Result<Self> {
+        Self::builder(api_key).build_async()
+    }
+
+    pub fn builder(api_key: impl Into<String>) -> OcrClientBuilder {
+        OcrClient::builder(api_key)
+    }
+
+    pub(crate) fn from_parts(http: reqwest::Client, config: ClientConfig) -> Self {
+        Self { http, config }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.config.base_url
+    }
+
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// OCR a file and write the result to `output_path`.
+    pub async fn run_ocr(
+        &self,
+        input_path: &Path,
+        options: &OcrOptions,
+        output_path: &Path,
+    ) -> Result<()> {
+        self.run_ocr_input(&OcrInput::from_path(input_path), options, output_path)
+            .await
+    }
+
+    /// OCR a file, in-memory document or URL and write the result to `output_path`.
+    pub async fn run_ocr_input(
+        &self,
+        input: &OcrInput<'_>,
+        options: &OcrOptions,
+        output_path: &Path,
+    ) -> Result<()> {
+        let mut ocr = self.process_raw(input, options).await?;
+        ocr.model.get_or_insert_with(|| self.config.model.clone());
+        write_output(output_path, ocr, source_info(input, options)?, options)?;
+        log_done(output_path, options);
+        Ok(())
+    }
+
+    /// Async counterpart of [`OcrClient::download`].
+    pub async fn download(&self, url: &str) -> Result<OcrInput<'static>> {
+        info!("Downloading {url}...");
+        let response = self.execute("Download", || self.http.get(url)).await?;
+        let mime_type = content_type(response.headers());
+        let input = downloaded_input(url, response.bytes().await?.to_vec(), mime_type);
+        Ok(input)
+    }
+
+    /// OCR a file and return the result without writing anything to disk.
+    pub async fn process(&self, input_path: &Path, options: &OcrOptions) -> Result<OcrDocument> {
+        self.process_input(&OcrInput::from_path(input_path), options)
+            .await
+    }
+
+    /// OCR a file or in-memory document and return the result.
+    pub async fn process_input(
+        &self,
+        input: &OcrInput<'_>,
+        options: &OcrOptions,
+    ) -> Result<OcrDocument> {
+        let ocr = self.process_raw(input, options).await?;
+        let source = source_info(input, options)?;
+        let mut document = OcrDocument::from_response(ocr, source.name, &self.config.model)?;
+        document.source_sha256 = source.sha256;
+        Ok(document)
+    }
+
+    /// Async counterpart of [`OcrClient::process_raw`].
+    pub async fn process_raw(
+        &self,
+        input: &OcrInput<'_>,
+        options: &OcrOptions,
+    ) -> Result<OcrResponse> {
+        let downloaded;
+        let input = match input.url() {
+            Some(url) if self.config.download_urls => {
+                downloaded = self.download(url).await?;
+                &downloaded
+            }
+            _ => input,
+        };
+        let parts = self.config.plan(input, options)?;
+        let mut responses = Vec::with_capacity(parts.len());
+        for part in &parts {
+            responses.push(self.process_part(input, part, options).await?);
+        }
+        let layouts: Vec<_> = parts.into_iter().map(|p| p.layout).collect();
+        Ok(self.config.assemble(&layouts, responses))
+    }
+
+    async fn process_part(
+        &self,
+        input: &OcrInput<'_>,
+        part: &RequestPart<'_>,
+        options: &OcrOptions,
+    ) -> Result<OcrResponse> {
+        let key = self.config.cache_key(part, options);
+        if let Some(response) = self.config.cached(key.as_deref(), part) {
+            return Ok(response);
+        }
+        let body = self.request_part(input, part, options).await?;
+        self.config.parse_and_cache(key.as_deref(), input, &body)
+    }
+
+    async fn request_part(
+        &self,
+        input: &OcrInput<'_>,
+        part: &RequestPart<'_>,
+        options: &OcrOptions,
+    ) -> Result<String> {
+        let delete_after = match self.config.upload {
+            UploadMode::Files { delete_after } if !part.is_remote() => delete_after,
+            _ => {
+                let request = self.config.build_request(input, part, options, None);
+                info!("Sending OCR request to Mistral API{}...", part.label());
+                return self.send(&request).await;
+            }
+        };
+
+        info!("Uploading file{}...", part.label());
+        let file_id = self
+            .upload_file(
+                &part.upload_name(input),
+                part.mime_type(),
+                part.data(),
+                FilePurpose::Ocr,
+            )
+            .await?;
+        let result = match self.signed_url(&file_id).await {
+            Ok(url) => {
+                let request = self.config.build_request(input, part, options, Some(&url));
+                info!("Sending OCR request to Mistral API{}...", part.label());
+                self.send(&request).await
+            }
+            Err(err) => Err(err),
+        };
+        if delete_after && let Err(err) = self.delete_file(&file_id).await {
+            warn!("Failed to delete uploaded file {file_id}: {err}");
+        }
+        result
+    }
+
+    async fn send(&self, request: &OcrRequest) -> Result<String> {
+        let url = self.config.url("/ocr");
+        let response = self
+            .execute("OCR request", || {
+                self.http
+                    .post(&url)
+                    .bearer_auth(&self.config.api_key)
+                    .json(request)
+            })
+            .await?;
+        Ok(response.text().await?)
+    }
+
+    /// Async counterpart of [`OcrClient::execute`].
+    pub(crate) async fn execute(
+        &self,
+        what: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        let response = loop {
+            attempt += 1;
+            tokio::time::sleep(self.config.rate_limit_wait()).await;
+            match build().send().await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let delay = self.config.retry_delay(
+                        what,
+                        attempt,
+                        is_transient_status(status),
+                        retry_after(status, resp.headers()),
+                        &format!("HTTP {status}"),
+                    );
+                    match delay {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => break resp,
+                    }
+                }
+                Err(err) => {
+                    match self.config.retry_delay(
+                        what,
+                        attempt,
+                        is_transient_error(&err),
+                        None,
+                        &err.to_string(),
+                    ) {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => return Err(err.into()),
+                    }
+                }
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(status, &headers, body));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RetryPolicy;
+    use std::time::Duration;
+
+    fn mock_body(text: &str) -> String {
+        serde_json::json!({
+            "pages": [{"index": 0, "markdown": text, "images": []}]
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn concurrent_documents_share_one_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/ocr")
+            .with_body(mock_body("async page"))
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = OcrClient::builder("key")
+            .base_url(server.url())
+            .build_async()
+            .unwrap();
+        let inputs: Vec<_> = (0..3)
+            .map(|i| OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name(format!("{i}.pdf")))
+            .collect();
+        let options = OcrOptions::default();
+        let results = tokio::join!(
+            client.process_input(&inputs[0], &options),
+            client.process_input(&inputs[1], &options),
+            client.process_input(&inputs[2], &options),
+        );
+
+        for (i, document) in [results.0, results.1, results.2].into_iter().enumerate() {
+            let document = document.unwrap();
+            assert_eq!(document.source_name, Some(format!("{i}.pdf")));
+            assert_eq!(document.pages[0].markdown, "async page");
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server
+            .mock("POST", "/ocr")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("POST", "/ocr")
+            .with_body(mock_body("after retry"))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = AsyncOcrClient::builder("key")
+            .base_url(server.url())
+            .retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+            })
+            .build_async()
+            .unwrap();
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+        let document = client
+            .process_input(&input, &OcrOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(document.pages[0].markdown, "after retry");
+        failing.assert_async().await;
+        ok.assert_async().await;
+    }
+}
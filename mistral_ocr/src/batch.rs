@@ -0,0 +1,511 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::client::{PartLayout, log_done, parse_json, source_info};
+use crate::error::IoContext;
+use crate::files::FilePurpose;
+use crate::{
+    BatchJob, OcrClient, OcrError, OcrOptions, OcrRequest, OcrResponse, Result, SourceInfo,
+    UploadMode, write_output,
+};
+
+/// A submitted Mistral batch job and what to do with its results.
+///
+/// Save it with [`BatchState::save`] right after submitting: the job runs server-side,
+/// so a later process can [`load`](BatchState::load) it to resume polling and collect
+/// the results.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchState {
+    pub job_id: String,
+    pub model: String,
+    pub options: OcrOptions,
+    pub entries: Vec<BatchEntry>,
+    /// Id of the uploaded file of batch requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_file: Option<String>,
+    /// Keep the request and result files in the account after collecting the results.
+    #[serde(default)]
+    pub keep_files: bool,
+}
+
+/// One input document of a batch job.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub name: Option<String>,
+    pub output: PathBuf,
+    /// Hex SHA-256 of the input, recorded at submission for front matter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Where to save the raw response, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_output: Option<PathBuf>,
+    /// One request per part; oversized PDFs are split as for synchronous requests.
+    parts: Vec<PartLayout>,
+}
+
+/// Server-side state of a batch job.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BatchStatus {
+    pub status: BatchJobStatus,
+    #[serde(default)]
+    pub total_requests: u64,
+    #[serde(default)]
+    pub succeeded_requests: u64,
+    #[serde(default)]
+    pub failed_requests: u64,
+    #[serde(default)]
+    output_file: Option<String>,
+    #[serde(default)]
+    error_file: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum BatchJobStatus {
+    Queued,
+    Running,
+    Success,
+    Failed,
+    TimeoutExceeded,
+    CancellationRequested,
+    Cancelled,
+    /// A status this version does not know about, which may not be final.
+    #[serde(other)]
+    Unknown,
+}
+
+impl BatchStatus {
+    /// Whether the job has stopped and will not make further progress. An
+    /// [`Unknown`](BatchJobStatus::Unknown) status is not taken to be final.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            BatchJobStatus::Success
+                | BatchJobStatus::Failed
+                | BatchJobStatus::TimeoutExceeded
+                | BatchJobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct BatchLine<'a> {
+    custom_id: String,
+    body: &'a OcrRequest,
+}
+
+#[derive(Serialize)]
+struct CreateJob<'a> {
+    input_files: [&'a str; 1],
+    endpoint: &'static str,
+    model: &'a str,
+}
+
+#[derive(Deserialize)]
+struct CreatedJob {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ResultLine {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<ResultResponse>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ResultResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+impl BatchState {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| OcrError::decode("Failed to serialize batch state", e))?;
+        fs::write(path, json).io_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .io_context(|| format!("Failed to read batch state {}", path.display()))?;
+        parse_json(&json, "Failed to parse batch state")
+    }
+}
+
+fn custom_id(entry: usize, part: usize) -> String {
+    format!("{entry}-{part}")
+}
+
+impl OcrClient {
+    /// Build one OCR request per job (per part for split PDFs), upload them as a JSONL
+    /// file and start a batch job.
+    ///
+    /// Documents are always embedded inline. The uploaded request file, and the result
+    /// files once [`collect_batch`](Self::collect_batch) has read them, are deleted unless
+    /// the client keeps uploads ([`UploadMode::Files`] without `delete_after`).
+    pub fn submit_batch(&self, jobs: &[BatchJob], options: &OcrOptions) -> Result<BatchState> {
+        // Requests embed whole documents, so they are spooled to disk rather than memory.
+        let spool = tempfile::tempfile().io_context(|| "Failed to create temp file")?;
+        let mut jsonl = BufWriter::new(spool);
+        let mut entries = Vec::with_capacity(jobs.len());
+        for (i, job) in jobs.iter().enumerate() {
+            let parts = self.config.plan(&job.input, options)?;
+            for (p, part) in parts.iter().enumerate() {
+                let request = self.config.build_request(&job.input, part, options, None);
+                let line = BatchLine {
+                    custom_id: custom_id(i, p),
+                    body: &request,
+                };
+                serde_json::to_writer(&mut jsonl, &line)
+                    .map_err(|e| OcrError::decode("Failed to serialize batch request", e))?;
+                jsonl
+                    .write_all(b"\n")
+                    .io_context(|| "Failed to write batch requests")?;
+            }
+            let source = source_info(&job.input, options)?;
+            entries.push(BatchEntry {
+                name: source.name,
+                sha256: source.sha256,
+                output: job.output.clone(),
+                raw_output: job.raw_output.clone(),
+                parts: parts.into_iter().map(|p| p.layout).collect(),
+            });
+        }
+
+        info!("Uploading batch of {} documents...", jobs.len());
+        let jsonl = jsonl
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .io_context(|| "Failed to write batch requests")?;
+        let file_id = self.upload_from_file(
+            "batch.jsonl",
+            "application/jsonl",
+            jsonl,
+            FilePurpose::Batch,
+        )?;
+        let keep_files = matches!(
+            self.config.upload,
+            UploadMode::Files {
+                delete_after: false
+            }
+        );
+        let body = CreateJob {
+            input_files: [&file_id],
+            endpoint: "/v1/ocr",
+            model: &self.config.model,
+        };
+        let created = self
+            .execute("Batch job creation", || {
+                self.http
+                    .post(self.config.url("/batch/jobs"))
+                    .bearer_auth(&self.config.api_key)
+                    .json(&body)
+            })
+            .and_then(|response| {
+                parse_json::<CreatedJob>(&response.text()?, "Failed to parse batch job")
+            });
+        let job = match created {
+            Ok(job) => job,
+            Err(err) => {
+                if !keep_files {
+                    self.delete_batch_file(&file_id);
+                }
+                return Err(err);
+            }
+        };
+        info!("Created batch job {}", job.id);
+
+        Ok(BatchState {
+            job_id: job.id,
+            model: self.config.model.clone(),
+            options: options.clone(),
+            entries,
+            input_file: Some(file_id),
+            keep_files,
+        })
+    }
+
+    /// Delete a file of the batch job; failing to is only a warning.
+    fn delete_batch_file(&self, file_id: &str) {
+        if let Err(err) = self.delete_file(file_id) {
+            warn!("Failed to delete batch file {file_id}: {err}");
+        }
+    }
+
+    pub fn batch_status(&self, state: &BatchState) -> Result<BatchStatus> {
+        let response = self.execute("Batch status request", || {
+            self.http
+                .get(self.config.url(&format!("/batch/jobs/{}", state.job_id)))
+                .bearer_auth(&self.config.api_key)
+        })?;
+        parse_json(&response.text()?, "Failed to parse batch status")
+    }
+
+    /// Poll the job every `interval` until it has finished.
+    pub fn wait_for_batch(&self, state: &BatchState, interval: Duration) -> Result<BatchStatus> {
+        loop {
+            let status = self.batch_status(state)?;
+            info!(
+                "Batch job {}: {:?}, {}/{} requests done ({} failed)",
+                state.job_id,
+                status.status,
+                status.succeeded_requests + status.failed_requests,
+                status.total_requests,
+                status.failed_requests
+            );
+            if status.is_finished() {
+                return Ok(status);
+            }
+            if status.status == BatchJobStatus::Unknown {
+                warn!(
+                    "Batch job {} has a status this version does not recognize; still waiting",
+                    state.job_id
+                );
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Download the results of a finished job and write each document's output.
+    ///
+    /// Returns one result per entry, in submission order; a document fails on its own
+    /// if any of its requests failed.
+    pub fn collect_batch(
+        &self,
+        state: &BatchState,
+        status: &BatchStatus,
+    ) -> Result<Vec<Result<()>>> {
+        let result_files = [&status.output_file, &status.error_file];
+        let mut results = HashMap::new();
+        for file_id in result_files.into_iter().flatten() {
+            for line in self
+                .file_content(file_id)?
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+            {
+                let line: ResultLine = parse_json(line, "Failed to parse batch result")?;
+                results.insert(line.custom_id.clone(), line);
+            }
+        }
+        if !state.keep_files {
+            let files = [&state.input_file].into_iter().chain(result_files);
+            for file_id in files.flatten() {
+                self.delete_batch_file(file_id);
+            }
+        }
+
+        if status.output_file.is_none() && status.status != BatchJobStatus::Success {
+            return Err(OcrError::Batch(format!(
+                "{} finished as {:?} without results",
+                state.job_id, status.status
+            )));
+        }
+
+        Ok(state
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mut ocr = self.entry_response(i, entry, &mut results)?;
+                ocr.model.get_or_insert_with(|| state.model.clone());
+                if let Some(raw_output) = &entry.raw_output {
+                    ocr.save(raw_output)?;
+                }
+                let source = SourceInfo {
+                    name: entry.name.clone(),
+                    sha256: entry.sha256.clone(),
+                };
+                write_output(&entry.output, ocr, source, &state.options)?;
+                log_done(&entry.output, &state.options);
+                Ok(())
+            })
+            .collect())
+    }
+
+    fn entry_response(
+        &self,
+        index: usize,
+        entry: &BatchEntry,
+        results: &mut HashMap<String, ResultLine>,
+    ) -> Result<OcrResponse> {
+        let mut responses = Vec::with_capacity(entry.parts.len());
+        for p in 0..entry.parts.len() {
+            let id = custom_id(index, p);
+            let line = results
+                .remove(&id)
+                .ok_or_else(|| OcrError::Batch(format!("no result for request {id}")))?;
+            responses.push(parse_result(line)?);
+        }
+        Ok(self.config.assemble(&entry.parts, responses))
+    }
+}
+
+fn parse_result(line: ResultLine) -> Result<OcrResponse> {
+    match line.response {
+        Some(response) if (200..300).contains(&response.status_code) => {
+            serde_json::from_value(response.body)
+                .map_err(|e| OcrError::decode("Failed to parse OCR response", e))
+        }
+        Some(response) => Err(OcrError::Api {
+            status: response.status_code,
+            body: response.body.to_string(),
+        }),
+        None => {
+            let error = line.error.map(|e| e.to_string()).unwrap_or_default();
+            Err(OcrError::Batch(format!(
+                "request {} failed: {error}",
+                line.custom_id
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OcrInput;
+    use mockito::Matcher;
+
+    fn result_line(custom_id: &str, status: u16, body: serde_json::Value) -> String {
+        serde_json::json!({
+            "id": format!("res-{custom_id}"),
+            "custom_id": custom_id,
+            "response": {"status_code": status, "body": body},
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn batch_is_submitted_resumed_from_state_and_collected() {
+        let mut server = mockito::Server::new();
+        let upload = server
+            .mock("POST", "/files")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#"name="purpose"\r\n\r\nbatch"#.to_string()),
+                Matcher::Regex(r#""custom_id":"0-0""#.to_string()),
+                Matcher::Regex(r#""custom_id":"1-0""#.to_string()),
+            ]))
+            .with_body(r#"{"id": "file-in"}"#)
+            .create();
+        let create = server
+            .mock("POST", "/batch/jobs")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "input_files": ["file-in"],
+                "endpoint": "/v1/ocr",
+                "model": "mistral-ocr-latest",
+            })))
+            .with_body(r#"{"id": "job-1", "status": "QUEUED"}"#)
+            .create();
+        server
+            .mock("GET", "/batch/jobs/job-1")
+            .with_body(
+                r#"{"id": "job-1", "status": "SUCCESS", "total_requests": 2,
+                    "succeeded_requests": 1, "failed_requests": 1, "output_file": "file-out"}"#,
+            )
+            .create();
+        let output_lines = [
+            result_line(
+                "1-0",
+                400,
+                serde_json::json!({"message": "document too complex"}),
+            ),
+            result_line(
+                "0-0",
+                200,
+                serde_json::json!({"pages": [{"index": 0, "markdown": "batched", "images": []}]}),
+            ),
+        ];
+        server
+            .mock("GET", "/files/file-out/content")
+            .with_body(output_lines.join("\n"))
+            .create();
+        let deletions: Vec<_> = ["file-in", "file-out"]
+            .iter()
+            .map(|id| {
+                server
+                    .mock("DELETE", format!("/files/{id}").as_str())
+                    .with_body("{}")
+                    .expect(1)
+                    .create()
+            })
+            .collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = OcrClient::builder("key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let jobs: Vec<_> = ["a.pdf", "b.pdf"]
+            .into_iter()
+            .map(|name| {
+                BatchJob::new(
+                    OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name(name),
+                    dir.path().join(name).with_extension("md"),
+                )
+                .with_raw_output(dir.path().join(name).with_extension("json"))
+            })
+            .collect();
+        let state = client.submit_batch(&jobs, &OcrOptions::default()).unwrap();
+        upload.assert();
+        create.assert();
+
+        let state_path = dir.path().join("batch.json");
+        state.save(&state_path).unwrap();
+        let state = BatchState::load(&state_path).unwrap();
+        assert_eq!(state.job_id, "job-1");
+
+        let status = client.wait_for_batch(&state, Duration::ZERO).unwrap();
+        assert_eq!(status.status, BatchJobStatus::Success);
+        let results = client.collect_batch(&state, &status).unwrap();
+
+        assert!(results[0].is_ok());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap().trim(),
+            "batched"
+        );
+        let raw = OcrResponse::load(&dir.path().join("a.json")).unwrap();
+        assert_eq!(raw.pages[0].markdown, "batched");
+        assert!(matches!(results[1], Err(OcrError::Api { status: 400, .. })));
+        for deletion in deletions {
+            deletion.assert();
+        }
+        assert!(!dir.path().join("b.md").exists());
+    }
+
+    #[test]
+    fn unknown_statuses_are_not_finished() {
+        let status = |json: &str| serde_json::from_str::<BatchStatus>(json).unwrap();
+        let unknown = status(r#"{"status": "VALIDATING"}"#);
+        assert_eq!(unknown.status, BatchJobStatus::Unknown);
+        assert!(!unknown.is_finished());
+        assert!(!status(r#"{"status": "CANCELLATION_REQUESTED"}"#).is_finished());
+        assert!(status(r#"{"status": "TIMEOUT_EXCEEDED"}"#).is_finished());
+    }
+
+    #[test]
+    fn missing_results_fail_their_entry_only() {
+        let mut results = HashMap::new();
+        let entry = BatchEntry {
+            name: None,
+            sha256: None,
+            output: PathBuf::from("x.md"),
+            raw_output: None,
+            parts: vec![PartLayout {
+                first_page: 0,
+                pages: None,
+            }],
+        };
+        let client = OcrClient::new("key").unwrap();
+        let err = client.entry_response(0, &entry, &mut results).unwrap_err();
+        assert!(matches!(err, OcrError::Batch(msg) if msg == "no result for request 0-0"));
+    }
+}
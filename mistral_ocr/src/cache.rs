@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::IoContext;
+use crate::{OcrError, Result};
+
+/// On-disk cache of raw OCR responses, keyed by the SHA-256 of the document bytes
+/// sent, the model, the page selection and other request settings.
+///
+/// Responses are stored with image data, so a cached document can be rendered again
+/// in any [`ImageMode`](crate::ImageMode) without another request. Nothing is ever
+/// evicted; see [`ResultCache::prune`].
+#[derive(Clone, Debug)]
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+/// A cached response, as listed by [`ResultCache::entries`].
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    pub key: String,
+    pub model: String,
+    /// File name of the document the response was first requested for.
+    pub source_name: Option<String>,
+    pub size: u64,
+    pub created: SystemTime,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheRecord {
+    model: String,
+    source_name: Option<String>,
+    /// Seconds since the Unix epoch.
+    created: u64,
+    response: serde_json::Value,
+}
+
+impl ResultCache {
+    /// Cache in `dir`, created on first write.
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache in the platform cache directory, e.g. `~/.cache/mistral_ocr` on Linux.
+    pub fn open_default() -> Result<Self> {
+        let base = dirs::cache_dir().ok_or_else(|| OcrError::Io {
+            context: "Failed to locate cache directory".to_string(),
+            source: io::Error::new(
+                io::ErrorKind::NotFound,
+                "no cache directory for this platform",
+            ),
+        })?;
+        Ok(Self::open(base.join("mistral_ocr")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cache key for one request's document bytes and the settings that shape the response.
+    ///
+    /// `settings` are any other request fields that change the response, such as
+    /// annotation formats, serialized.
+    pub(crate) fn key(
+        model: &str,
+        pages: Option<&str>,
+        settings: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> String {
+        let mut hasher = Sha256::new();
+        for field in [model, pages.unwrap_or(""), settings, mime_type] {
+            hasher.update(field.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update(data);
+        to_hex(&hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    /// Raw response JSON stored under `key`, if any.
+    pub(crate) fn get(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let json = match fs::read_to_string(self.path(key)) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).io_context(|| format!("Failed to read cache entry {key}")),
+        };
+        let record: CacheRecord = serde_json::from_str(&json)
+            .map_err(|e| OcrError::decode(format!("Corrupt cache entry {key}"), e))?;
+        Ok(Some(record.response))
+    }
+
+    pub(crate) fn put(
+        &self,
+        key: &str,
+        model: &str,
+        source_name: Option<&str>,
+        response: serde_json::Value,
+    ) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .io_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let record = CacheRecord {
+            model: model.to_string(),
+            source_name: source_name.map(str::to_string),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            response,
+        };
+        let json = serde_json::to_string(&record)
+            .map_err(|e| OcrError::decode("Failed to serialize cache entry", e))?;
+        // Write to a temp file first so concurrent readers never see a partial entry.
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)
+            .io_context(|| format!("Failed to create temp file in {}", self.dir.display()))?;
+        tmp.write_all(json.as_bytes())
+            .io_context(|| "Failed to write cache entry")?;
+        tmp.persist(self.path(key))
+            .map_err(|e| e.error)
+            .io_context(|| format!("Failed to write cache entry {key}"))?;
+        Ok(())
+    }
+
+    /// All cached responses, oldest first. Unreadable entries are skipped.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).io_context(|| format!("Failed to read {}", self.dir.display()));
+            }
+        };
+        let mut entries = Vec::new();
+        for path in dir.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let Some(key) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            let Ok(json) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok(record) = serde_json::from_str::<CacheRecord>(&json) else {
+                continue;
+            };
+            entries.push(CacheEntry {
+                key: key.to_string(),
+                model: record.model,
+                source_name: record.source_name,
+                size: json.len() as u64,
+                created: UNIX_EPOCH + Duration::from_secs(record.created),
+            });
+        }
+        entries.sort_by_key(|e| e.created);
+        Ok(entries)
+    }
+
+    /// Remove every cached response; returns how many were removed.
+    pub fn clear(&self) -> Result<usize> {
+        self.remove_where(|_| true)
+    }
+
+    /// Remove responses cached more than `max_age` ago; returns how many were removed.
+    pub fn prune(&self, max_age: Duration) -> Result<usize> {
+        let cutoff = SystemTime::now().checked_sub(max_age).unwrap_or(UNIX_EPOCH);
+        self.remove_where(|entry| entry.created < cutoff)
+    }
+
+    fn remove_where(&self, remove: impl Fn(&CacheEntry) -> bool) -> Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()?.iter().filter(|e| remove(e)) {
+            let path = self.path(&entry.key);
+            fs::remove_file(&path).io_context(|| format!("Failed to remove {}", path.display()))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_depend_on_every_input() {
+        let base = ResultCache::key("m", None, "{}", "application/pdf", b"doc");
+        assert_eq!(base.len(), 64);
+        assert_eq!(
+            base,
+            ResultCache::key("m", None, "{}", "application/pdf", b"doc")
+        );
+        assert_ne!(
+            base,
+            ResultCache::key("m2", None, "{}", "application/pdf", b"doc")
+        );
+        assert_ne!(
+            base,
+            ResultCache::key("m", Some("1-2"), "{}", "application/pdf", b"doc")
+        );
+        assert_ne!(base, ResultCache::key("m", None, "{}", "image/png", b"doc"));
+        assert_ne!(
+            base,
+            ResultCache::key("m", None, r#"{"a":1}"#, "application/pdf", b"doc")
+        );
+        assert_ne!(
+            base,
+            ResultCache::key("m", None, "{}", "application/pdf", b"doc2")
+        );
+    }
+
+    #[test]
+    fn entries_can_be_listed_pruned_and_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResultCache::open(dir.path().join("cache"));
+        assert!(cache.entries().unwrap().is_empty());
+
+        let response = serde_json::json!({"pages": []});
+        cache
+            .put("aaa", "m", Some("a.pdf"), response.clone())
+            .unwrap();
+        cache.put("bbb", "m", None, response.clone()).unwrap();
+        assert_eq!(cache.get("aaa").unwrap(), Some(response));
+        assert_eq!(cache.get("missing").unwrap(), None);
+
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.source_name.as_deref() == Some("a.pdf"))
+        );
+
+        assert_eq!(cache.prune(Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(cache.prune(Duration::ZERO).unwrap(), 2);
+        cache.put("ccc", "m", None, serde_json::json!({})).unwrap();
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,893 @@
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, RETRY_AFTER};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::ResultCache;
+use crate::files::FilePurpose;
+use crate::input::{InputKind, kind_for_mime, url_file_name};
+use crate::rate_limit::RateLimiter;
+use crate::split::{renumber_images, split_pdf};
+use crate::{
+    AnnotationSchema, Document, ImageMode, MAX_FILE_SIZE, OcrDocument, OcrError, OcrInput,
+    OcrOptions, OcrRequest, OcrResponse, PageSelection, Result, SourceInfo, UploadMode,
+    write_output,
+};
+
+/// Base URL of the public Mistral API; endpoint paths such as `/ocr` are appended to it.
+pub const DEFAULT_BASE_URL: &str = "https://api.mistral.ai/v1";
+
+pub const DEFAULT_MODEL: &str = "mistral-ocr-latest";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+const DEFAULT_USER_AGENT: &str = concat!("mistral_ocr/", env!("CARGO_PKG_VERSION"));
+
+/// How transient failures (HTTP 429, 5xx, timeouts and connection errors) are retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every retry after that.
+    pub initial_delay: Duration,
+    /// Longest wait before a retry, including one asked for by a `Retry-After` header.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Send every request exactly once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Settings shared by the blocking and async clients.
+#[derive(Clone, Debug)]
+pub(crate) struct ClientConfig {
+    pub(crate) api_key: String,
+    pub(crate) base_url: String,
+    pub(crate) model: String,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) max_file_size: u64,
+    pub(crate) upload: UploadMode,
+    pub(crate) download_urls: bool,
+    pub(crate) rate_limit: Option<Arc<RateLimiter>>,
+    pub(crate) cache: Option<ResultCache>,
+}
+
+/// One request's worth of a document: the whole input, or a chunk of an oversized PDF.
+pub(crate) struct RequestPart<'a> {
+    kind: InputKind,
+    data: Cow<'a, [u8]>,
+    /// Set for remote inputs, which the API fetches itself; `data` is then empty.
+    remote_url: Option<&'a str>,
+    /// Number of pages in a chunk; `None` when the part is the whole document.
+    page_count: Option<u32>,
+    pub(crate) layout: PartLayout,
+}
+
+/// Where a part's pages sit in the original document; all that is needed to stitch
+/// its response back in, so it is kept alongside pending batch jobs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PartLayout {
+    /// Zero-based index of the part's first page in the original document.
+    pub(crate) first_page: u32,
+    /// Pages to request, relative to `first_page`.
+    pub(crate) pages: Option<PageSelection>,
+}
+
+impl RequestPart<'_> {
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub(crate) fn is_remote(&self) -> bool {
+        self.remote_url.is_some()
+    }
+
+    pub(crate) fn mime_type(&self) -> &'static str {
+        match self.kind {
+            InputKind::Pdf | InputKind::Convertible(_) => "application/pdf",
+            InputKind::Image(mime) => mime,
+        }
+    }
+
+    /// File name for uploads: the input's stem, with a `.pdf` extension once converted.
+    pub(crate) fn upload_name(&self, input: &OcrInput) -> String {
+        let name = Path::new(input.file_name().unwrap_or("document"));
+        let stem = name
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "document".to_string());
+        match self.kind {
+            InputKind::Pdf | InputKind::Convertible(_) => format!("{stem}.pdf"),
+            InputKind::Image(_) => name.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Suffix for log messages, e.g. ` for pages 41-80`.
+    pub(crate) fn label(&self) -> String {
+        match self.page_count {
+            Some(count) => format!(
+                " for pages {}-{}",
+                self.layout.first_page + 1,
+                self.layout.first_page + count
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+impl ClientConfig {
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// How long to wait before the next request to respect the rate limit.
+    pub(crate) fn rate_limit_wait(&self) -> Duration {
+        self.rate_limit
+            .as_ref()
+            .map_or(Duration::ZERO, |limiter| limiter.reserve())
+    }
+
+    /// Convert and size-check `input`, splitting PDFs over the upload limit into
+    /// page-range chunks that are sent as separate requests.
+    pub(crate) fn plan<'a>(
+        &self,
+        input: &'a OcrInput,
+        options: &OcrOptions,
+    ) -> Result<Vec<RequestPart<'a>>> {
+        if let Some(url) = input.url() {
+            return Ok(vec![RequestPart {
+                kind: input.remote_kind(),
+                data: Cow::Borrowed(&[]),
+                remote_url: Some(url),
+                page_count: None,
+                layout: PartLayout {
+                    first_page: 0,
+                    pages: options.pages.clone(),
+                },
+            }]);
+        }
+
+        let (kind, data) = input.prepare()?;
+
+        let file_size = data.len() as u64;
+        if file_size <= self.max_file_size {
+            let pages = match (kind, &options.pages) {
+                (InputKind::Image(_), Some(_)) => {
+                    warn!("Page selection ignored for single-page image input");
+                    None
+                }
+                (_, pages) => pages.clone(),
+            };
+            return Ok(vec![RequestPart {
+                kind,
+                data,
+                remote_url: None,
+                page_count: None,
+                layout: PartLayout {
+                    first_page: 0,
+                    pages,
+                },
+            }]);
+        }
+        if kind != InputKind::Pdf {
+            return Err(OcrError::FileTooLarge {
+                size: file_size,
+                limit: self.max_file_size,
+            });
+        }
+
+        info!(
+            "File is {:.1} MB, over the {} MB upload limit; splitting into page ranges...",
+            file_size as f64 / (1024.0 * 1024.0),
+            self.max_file_size / (1024 * 1024)
+        );
+        let mut parts = Vec::new();
+        for chunk in split_pdf(&data, self.max_file_size)? {
+            let range = chunk.first_page..chunk.first_page + chunk.page_count;
+            let pages = match &options.pages {
+                None => None,
+                Some(selection) => {
+                    let local: Vec<u32> = selection
+                        .indices()
+                        .iter()
+                        .filter(|i| range.contains(i))
+                        .map(|i| i - chunk.first_page)
+                        .collect();
+                    if local.is_empty() {
+                        continue;
+                    }
+                    Some(PageSelection::from_indices(local))
+                }
+            };
+            parts.push(RequestPart {
+                kind,
+                data: Cow::Owned(chunk.data),
+                remote_url: None,
+                page_count: Some(chunk.page_count),
+                layout: PartLayout {
+                    first_page: chunk.first_page,
+                    pages,
+                },
+            });
+        }
+        Ok(parts)
+    }
+
+    /// Whether to ask for image data: when `options` render images, and always for
+    /// responses that go into the cache, so that a cached response serves every image mode.
+    fn requests_images(&self, part: &RequestPart, options: &OcrOptions) -> bool {
+        options.image_mode != ImageMode::None || (self.cache.is_some() && !part.is_remote())
+    }
+
+    /// OCR request body for one planned part.
+    ///
+    /// The document is referenced by its remote URL, or by `uploaded_url` when it went
+    /// through the Files API, and embedded as a base64 data URI otherwise.
+    pub(crate) fn build_request(
+        &self,
+        input: &OcrInput,
+        part: &RequestPart,
+        options: &OcrOptions,
+        uploaded_url: Option<&str>,
+    ) -> OcrRequest {
+        let url = match uploaded_url.or(part.remote_url) {
+            Some(url) => url.to_string(),
+            None => {
+                info!("Encoding file...");
+                format!(
+                    "data:{};base64,{}",
+                    part.mime_type(),
+                    BASE64.encode(&part.data)
+                )
+            }
+        };
+
+        let document = match part.kind {
+            InputKind::Pdf => Document::DocumentUrl {
+                document_url: url,
+                document_name: input.file_name().unwrap_or_default().to_string(),
+            },
+            InputKind::Image(_) => Document::ImageUrl { image_url: url },
+            InputKind::Convertible(_) => unreachable!("office documents are converted to PDF"),
+        };
+
+        OcrRequest {
+            model: self.model.clone(),
+            document,
+            include_image_base64: self.requests_images(part, options).then_some(true),
+            pages: part.layout.pages.as_ref().map(|p| p.indices().to_vec()),
+            document_annotation_format: options
+                .document_annotation_format
+                .as_ref()
+                .map(AnnotationSchema::response_format),
+            bbox_annotation_format: options
+                .bbox_annotation_format
+                .as_ref()
+                .map(AnnotationSchema::response_format),
+            extract_header: options.extract_headers.then_some(true),
+            extract_footer: options.extract_headers.then_some(true),
+        }
+    }
+
+    /// Cache key for a part, or `None` when caching is off or the part is remote.
+    pub(crate) fn cache_key(&self, part: &RequestPart, options: &OcrOptions) -> Option<String> {
+        self.cache.as_ref()?;
+        if part.is_remote() {
+            return None;
+        }
+        let pages = part.layout.pages.as_ref().map(|p| p.to_string());
+        let mut settings = serde_json::Map::new();
+        let formats = [
+            (
+                "document_annotation_format",
+                &options.document_annotation_format,
+            ),
+            ("bbox_annotation_format", &options.bbox_annotation_format),
+        ];
+        for (field, format) in formats {
+            if let Some(format) = format {
+                settings.insert(field.to_string(), format.response_format());
+            }
+        }
+        if options.extract_headers {
+            settings.insert("extract_header_footer".to_string(), true.into());
+        }
+        Some(ResultCache::key(
+            &self.model,
+            pages.as_deref(),
+            &serde_json::Value::from(settings).to_string(),
+            part.mime_type(),
+            part.data(),
+        ))
+    }
+
+    /// Cached response for `key`. Unreadable entries are treated as misses.
+    pub(crate) fn cached(&self, key: Option<&str>, part: &RequestPart) -> Option<OcrResponse> {
+        let (cache, key) = (self.cache.as_ref()?, key?);
+        let cached = cache.get(key).and_then(|value| {
+            value
+                .map(|v| {
+                    serde_json::from_value(v)
+                        .map_err(|e| OcrError::decode("Corrupt cache entry", e))
+                })
+                .transpose()
+        });
+        match cached {
+            Ok(Some(response)) => {
+                info!("Using cached OCR result{}", part.label());
+                Some(response)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                warn!("Ignoring cache entry {key}: {err}");
+                None
+            }
+        }
+    }
+
+    /// Parse a response body and store it under `key`. Failing to write the cache is
+    /// only a warning; the response is still returned.
+    pub(crate) fn parse_and_cache(
+        &self,
+        key: Option<&str>,
+        input: &OcrInput,
+        body: &str,
+    ) -> Result<OcrResponse> {
+        info!("Processing response...");
+        let response = parse_response(body)?;
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            let stored = parse_json(body, "Failed to parse OCR response")
+                .and_then(|value| cache.put(key, &self.model, input.file_name(), value));
+            if let Err(err) = stored {
+                warn!("Failed to cache OCR result: {err}");
+            }
+        }
+        Ok(response)
+    }
+
+    /// Stitch the responses for all parts back into one, with page indices and image
+    /// ids relative to the original document.
+    pub(crate) fn assemble(
+        &self,
+        layouts: &[PartLayout],
+        responses: Vec<OcrResponse>,
+    ) -> OcrResponse {
+        let mut assembled: Option<OcrResponse> = None;
+        let mut used_ids = HashSet::new();
+        let mut next_image = 0;
+        let mut annotation_dropped = false;
+        for (layout, mut response) in layouts.iter().zip(responses) {
+            if let Some(selection) = &layout.pages {
+                selection.restore_indices(&mut response.pages);
+            }
+            for page in &mut response.pages {
+                page.index += layout.first_page;
+            }
+            if layouts.len() > 1 {
+                renumber_images(&mut response.pages, &mut used_ids, &mut next_image);
+            }
+            let Some(total) = &mut assembled else {
+                assembled = Some(response);
+                continue;
+            };
+            // Other top-level fields such as the model name are the same for every part.
+            if response.document_annotation.is_some() && !annotation_dropped {
+                annotation_dropped = true;
+                warn!(
+                    "Document was split for upload; its document annotation only covers \
+                     pages before {}",
+                    layout.first_page + 1
+                );
+            }
+            total.pages.extend(response.pages);
+            match (&mut total.usage_info, response.usage_info) {
+                (Some(usage), Some(more)) => usage.add(&more),
+                (usage, more) => *usage = usage.or(more),
+            }
+        }
+        assembled.unwrap_or_default()
+    }
+
+    /// Delay before retrying after failed attempt number `attempt`, or `None` to give up.
+    ///
+    /// A `retry_after` sent by the API replaces the backoff delay, capped at
+    /// [`RetryPolicy::max_delay`].
+    pub(crate) fn retry_delay(
+        &self,
+        what: &str,
+        attempt: u32,
+        transient: bool,
+        retry_after: Option<Duration>,
+        reason: &str,
+    ) -> Option<Duration> {
+        let max_attempts = self.retry.max_attempts.max(1);
+        if !transient || attempt >= max_attempts {
+            return None;
+        }
+        let delay = match retry_after {
+            Some(delay) => delay.min(self.retry.max_delay),
+            None => self.retry.delay_for(attempt),
+        };
+        warn!(
+            "{what} failed ({reason}), retrying in {}s (attempt {attempt}/{max_attempts})...",
+            delay.as_secs()
+        );
+        Some(delay)
+    }
+}
+
+pub(crate) fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+pub(crate) fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+/// The `Retry-After` delay (in seconds) of an HTTP 429 response.
+pub(crate) fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Error for a non-success response that is not going to be retried.
+pub(crate) fn status_error(status: StatusCode, headers: &HeaderMap, body: String) -> OcrError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = retry_after(status, headers);
+        OcrError::RateLimited { retry_after, body }
+    } else {
+        OcrError::Api {
+            status: status.as_u16(),
+            body,
+        }
+    }
+}
+
+pub(crate) fn parse_response(body: &str) -> Result<OcrResponse> {
+    parse_json(body, "Failed to parse OCR response")
+}
+
+/// `Content-Type` of a download, if it names a type we can OCR. Generic types such as
+/// `application/octet-stream` are dropped so the file name decides instead.
+pub(crate) fn content_type(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    kind_for_mime(value).map(|_| value.to_string())
+}
+
+pub(crate) fn downloaded_input(
+    url: &str,
+    data: Vec<u8>,
+    mime_type: Option<String>,
+) -> OcrInput<'static> {
+    let mut input = OcrInput::from_bytes(data);
+    if let Some(name) = url_file_name(url) {
+        input = input.with_file_name(name);
+    }
+    if let Some(mime) = mime_type {
+        input = input.with_mime_type(mime);
+    }
+    input
+}
+
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &str, context: &str) -> Result<T> {
+    serde_json::from_str(body).map_err(|e| OcrError::decode(context, e))
+}
+
+/// Reusable client for the Mistral OCR API.
+///
+/// Holds a single HTTP connection pool, so one client should be shared across
+/// all documents instead of building a new one per call.
+#[derive(Clone, Debug)]
+pub struct OcrClient {
+    pub(crate) http: reqwest::blocking::Client,
+    pub(crate) config: ClientConfig,
+}
+
+/// Builder for [`OcrClient`], created with [`OcrClient::builder`].
+#[derive(Clone, Debug)]
+pub struct OcrClientBuilder {
+    api_key: String,
+    base_url: String,
+    model: String,
+    timeout: Duration,
+    user_agent: String,
+    retry: RetryPolicy,
+    max_file_size: u64,
+    upload: UploadMode,
+    download_urls: bool,
+    requests_per_minute: Option<u32>,
+    cache: Option<ResultCache>,
+}
+
+impl OcrClientBuilder {
+    /// API base URL, e.g. a proxy or a local mock server. Defaults to [`DEFAULT_BASE_URL`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// OCR model name, e.g. a pinned version instead of [`DEFAULT_MODEL`].
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Timeout for a single HTTP request. Defaults to 5 minutes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Largest document sent in one request. Bigger PDFs are split into page ranges,
+    /// other inputs are rejected. Defaults to Mistral's 50 MB upload limit.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// How documents are handed to the OCR endpoint. Defaults to [`UploadMode::Inline`].
+    pub fn upload_mode(mut self, upload: UploadMode) -> Self {
+        self.upload = upload;
+        self
+    }
+
+    /// Download URL inputs locally (see [`OcrClient::download`]) instead of letting the
+    /// API fetch them. Off by default.
+    pub fn download_urls(mut self, download: bool) -> Self {
+        self.download_urls = download;
+        self
+    }
+
+    /// Cap the number of API requests per minute across all clones of the client,
+    /// e.g. to stay under an account's rate limit when processing documents
+    /// concurrently. Uploads, page-range chunks and retries each count as a request.
+    pub fn requests_per_minute(mut self, requests: u32) -> Self {
+        self.requests_per_minute = Some(requests);
+        self
+    }
+
+    /// Reuse responses for documents that were already processed with the same model,
+    /// page selection and request settings such as annotation schemas. With a cache,
+    /// image data is always requested for local documents, so that a cached response can
+    /// be rendered in any image mode; [`ImageMode::None`] just leaves the images out.
+    pub fn cache(mut self, cache: ResultCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn build(self) -> Result<OcrClient> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?;
+        Ok(OcrClient {
+            http,
+            config: self.into_config(),
+        })
+    }
+
+    /// Build an [`AsyncOcrClient`](crate::AsyncOcrClient) with the same settings.
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<crate::AsyncOcrClient> {
+        let http = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?;
+        Ok(crate::AsyncOcrClient::from_parts(http, self.into_config()))
+    }
+
+    fn into_config(self) -> ClientConfig {
+        ClientConfig {
+            api_key: self.api_key,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            model: self.model,
+            retry: self.retry,
+            max_file_size: self.max_file_size,
+            upload: self.upload,
+            download_urls: self.download_urls,
+            rate_limit: self
+                .requests_per_minute
+                .map(|n| Arc::new(RateLimiter::per_minute(n))),
+            cache: self.cache,
+        }
+    }
+}
+
+impl OcrClient {
+    /// Client with default settings for the public Mistral API.
+    pub fn new(api_key: impl Into<String>) -> Result<Self> {
+        Self::builder(api_key).build()
+    }
+
+    pub fn builder(api_key: impl Into<String>) -> OcrClientBuilder {
+        OcrClientBuilder {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            retry: RetryPolicy::default(),
+            max_file_size: MAX_FILE_SIZE,
+            upload: UploadMode::default(),
+            download_urls: false,
+            requests_per_minute: None,
+            cache: None,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.config.base_url
+    }
+
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// OCR a file and write the result to `output_path`.
+    pub fn run_ocr(
+        &self,
+        input_path: &Path,
+        options: &OcrOptions,
+        output_path: &Path,
+    ) -> Result<()> {
+        self.run_ocr_input(&OcrInput::from_path(input_path), options, output_path)
+    }
+
+    /// OCR a file, in-memory document or URL and write the result to `output_path`.
+    pub fn run_ocr_input(
+        &self,
+        input: &OcrInput,
+        options: &OcrOptions,
+        output_path: &Path,
+    ) -> Result<()> {
+        self.write_outputs(input, options, output_path, None)
+    }
+
+    /// OCR `input`, write its markdown to `output_path` and, with `raw_path`, save the
+    /// raw response there too.
+    pub(crate) fn write_outputs(
+        &self,
+        input: &OcrInput,
+        options: &OcrOptions,
+        output_path: &Path,
+        raw_path: Option<&Path>,
+    ) -> Result<()> {
+        let mut ocr = self.process_raw(input, options)?;
+        // Record the model in the raw response too, so `render` can name it.
+        ocr.model.get_or_insert_with(|| self.config.model.clone());
+        if let Some(raw_path) = raw_path {
+            ocr.save(raw_path)?;
+        }
+        write_output(output_path, ocr, source_info(input, options)?, options)?;
+        log_done(output_path, options);
+        Ok(())
+    }
+
+    /// Download a remote document so it can be processed like a local one, for URLs
+    /// the API cannot reach itself (intranets, expiring links, auth cookies).
+    ///
+    /// The type is taken from the `Content-Type` header when it names a supported type,
+    /// and from the URL's file name otherwise.
+    pub fn download(&self, url: &str) -> Result<OcrInput<'static>> {
+        info!("Downloading {url}...");
+        let response = self.execute("Download", || self.http.get(url))?;
+        let mime_type = content_type(response.headers());
+        let input = downloaded_input(url, response.bytes()?.to_vec(), mime_type);
+        Ok(input)
+    }
+
+    /// OCR a file and return the result without writing anything to disk.
+    ///
+    /// Image data is only requested when `options.image_mode` is not [`ImageMode::None`].
+    pub fn process(&self, input_path: &Path, options: &OcrOptions) -> Result<OcrDocument> {
+        self.process_input(&OcrInput::from_path(input_path), options)
+    }
+
+    /// OCR a file or in-memory document and return the result.
+    pub fn process_input(&self, input: &OcrInput, options: &OcrOptions) -> Result<OcrDocument> {
+        let ocr = self.process_raw(input, options)?;
+        let source = source_info(input, options)?;
+        let mut document = OcrDocument::from_response(ocr, source.name, &self.config.model)?;
+        document.source_sha256 = source.sha256;
+        Ok(document)
+    }
+
+    /// OCR a document and return the API response as-is, e.g. to archive it with
+    /// [`OcrResponse::save`] and render it later.
+    ///
+    /// With [`ImageMode::None`] the API is not asked for image data, so the response
+    /// cannot be rendered with images afterwards, unless a cache is configured: cached
+    /// responses always include image data.
+    pub fn process_raw(&self, input: &OcrInput, options: &OcrOptions) -> Result<OcrResponse> {
+        let downloaded;
+        let input = match input.url() {
+            Some(url) if self.config.download_urls => {
+                downloaded = self.download(url)?;
+                &downloaded
+            }
+            _ => input,
+        };
+        let parts = self.config.plan(input, options)?;
+        let mut responses = Vec::with_capacity(parts.len());
+        for part in &parts {
+            responses.push(self.process_part(input, part, options)?);
+        }
+        let layouts: Vec<_> = parts.into_iter().map(|p| p.layout).collect();
+        Ok(self.config.assemble(&layouts, responses))
+    }
+
+    fn process_part(
+        &self,
+        input: &OcrInput,
+        part: &RequestPart,
+        options: &OcrOptions,
+    ) -> Result<OcrResponse> {
+        let key = self.config.cache_key(part, options);
+        if let Some(response) = self.config.cached(key.as_deref(), part) {
+            return Ok(response);
+        }
+        let body = self.request_part(input, part, options)?;
+        self.config.parse_and_cache(key.as_deref(), input, &body)
+    }
+
+    /// Send one part, uploading it first in [`UploadMode::Files`], and return the raw
+    /// response body.
+    fn request_part(
+        &self,
+        input: &OcrInput,
+        part: &RequestPart,
+        options: &OcrOptions,
+    ) -> Result<String> {
+        let delete_after = match self.config.upload {
+            UploadMode::Files { delete_after } if !part.is_remote() => delete_after,
+            _ => {
+                let request = self.config.build_request(input, part, options, None);
+                info!("Sending OCR request to Mistral API{}...", part.label());
+                return self.send(&request);
+            }
+        };
+
+        info!("Uploading file{}...", part.label());
+        let file_id = self.upload_file(
+            &part.upload_name(input),
+            part.mime_type(),
+            part.data(),
+            FilePurpose::Ocr,
+        )?;
+        let result = self.signed_url(&file_id).and_then(|url| {
+            let request = self.config.build_request(input, part, options, Some(&url));
+            info!("Sending OCR request to Mistral API{}...", part.label());
+            self.send(&request)
+        });
+        if delete_after && let Err(err) = self.delete_file(&file_id) {
+            warn!("Failed to delete uploaded file {file_id}: {err}");
+        }
+        result
+    }
+
+    fn send(&self, request: &OcrRequest) -> Result<String> {
+        let url = self.config.url("/ocr");
+        let response = self.execute("OCR request", || {
+            self.http
+                .post(&url)
+                .bearer_auth(&self.config.api_key)
+                .json(request)
+        })?;
+        Ok(response.text()?)
+    }
+
+    /// Send a request built by `build`, retrying transient failures per the retry policy.
+    ///
+    /// Non-success responses that are not retried become errors.
+    pub(crate) fn execute(
+        &self,
+        what: &str,
+        build: impl Fn() -> reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        let mut attempt = 0;
+        let response = loop {
+            attempt += 1;
+            std::thread::sleep(self.config.rate_limit_wait());
+            match build().send() {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let delay = self.config.retry_delay(
+                        what,
+                        attempt,
+                        is_transient_status(status),
+                        retry_after(status, resp.headers()),
+                        &format!("HTTP {status}"),
+                    );
+                    match delay {
+                        Some(delay) => std::thread::sleep(delay),
+                        None => break resp,
+                    }
+                }
+                Err(err) => {
+                    match self.config.retry_delay(
+                        what,
+                        attempt,
+                        is_transient_error(&err),
+                        None,
+                        &err.to_string(),
+                    ) {
+                        Some(delay) => std::thread::sleep(delay),
+                        None => return Err(err.into()),
+                    }
+                }
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let body = response.text().unwrap_or_default();
+            return Err(status_error(status, &headers, body));
+        }
+        Ok(response)
+    }
+}
+
+/// What to record about `input` in the output; the SHA-256 is only computed for
+/// [`OcrOptions::front_matter`].
+pub(crate) fn source_info(input: &OcrInput, options: &OcrOptions) -> Result<SourceInfo> {
+    Ok(SourceInfo {
+        name: input.file_name().map(str::to_string),
+        sha256: if options.front_matter {
+            input.sha256()?
+        } else {
+            None
+        },
+    })
+}
+
+pub(crate) fn log_done(output_path: &Path, options: &OcrOptions) {
+    let written = options.written_path(output_path);
+    info!("Done! Output written to {}", written.display());
+}
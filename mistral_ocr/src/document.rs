@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+use crate::annotation::parse_annotation;
+use crate::{
+    BoundingBox, OcrResponse, PageDimensions, Result, UsageInfo, decode_image_base64, mime_for_ext,
+};
+
+/// OCR result for a single document, with images already decoded.
+///
+/// Produced by [`OcrClient::process`](crate::OcrClient::process), or from a saved
+/// response with [`from_raw`](Self::from_raw), and rendered to disk with
+/// [`write_markdown`](crate::write_markdown).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OcrDocument {
+    /// File name of the source document, if known.
+    pub source_name: Option<String>,
+    /// Hex SHA-256 of the source document, if it was computed; see
+    /// [`OcrOptions::front_matter`](crate::OcrOptions::front_matter).
+    pub source_sha256: Option<String>,
+    /// Model that produced this result, as reported by the API, or else the model that
+    /// was asked for.
+    pub model: String,
+    /// Pages processed and document size, as reported by the API.
+    pub usage_info: Option<UsageInfo>,
+    /// The document schema filled in by the API, if one was requested; see
+    /// [`OcrOptions::document_annotation_format`](crate::OcrOptions::document_annotation_format).
+    pub document_annotation: Option<serde_json::Value>,
+    pub pages: Vec<DocumentPage>,
+}
+
+/// The document an OCR response was produced from, for [`write_output`](crate::write_output).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceInfo {
+    /// File name of the source document, if known.
+    pub name: Option<String>,
+    /// Hex SHA-256 of the source document, if known.
+    pub sha256: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentPage {
+    /// Zero-based page index within the source document.
+    pub index: u32,
+    /// Page content; images are referenced by their id, e.g. `![img-0.jpeg](img-0.jpeg)`.
+    pub markdown: String,
+    pub images: Vec<DocumentImage>,
+    /// Size of the page as rendered for OCR; image bounding boxes are relative to it.
+    pub dimensions: Option<PageDimensions>,
+    /// Running header and footer that the API extracted from the page, and that are
+    /// therefore not in `markdown`; see [`OcrOptions::extract_headers`](crate::OcrOptions::extract_headers).
+    pub header: Option<String>,
+    pub footer: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentImage {
+    /// Image id as referenced from the page markdown.
+    pub id: String,
+    pub mime_type: String,
+    /// Decoded image bytes; `None` when image data was not requested.
+    pub data: Option<Vec<u8>>,
+    /// Where the image was cut out of the page, in the pixels of
+    /// [`DocumentPage::dimensions`], e.g. to crop it from the original scan.
+    pub bbox: Option<BoundingBox>,
+    /// The bounding box schema filled in by the API for this image, if one was requested;
+    /// see [`OcrOptions::bbox_annotation_format`](crate::OcrOptions::bbox_annotation_format).
+    pub annotation: Option<serde_json::Value>,
+}
+
+impl OcrDocument {
+    /// Decode a saved raw response, e.g. one written by [`OcrResponse::save`], to render
+    /// it again without another request. The model is the one the response names, if any.
+    pub fn from_raw(response: OcrResponse, source_name: Option<String>) -> Result<Self> {
+        Self::from_response(response, source_name, "")
+    }
+
+    /// Decode `response`; `model` is used if the response does not name its model.
+    pub(crate) fn from_response(
+        response: OcrResponse,
+        source_name: Option<String>,
+        model: &str,
+    ) -> Result<Self> {
+        let model = response.model.unwrap_or_else(|| model.to_string());
+        let usage_info = response.usage_info;
+        let document_annotation = response.document_annotation.map(parse_annotation);
+        let mut pages = Vec::with_capacity(response.pages.len());
+        for page in response.pages {
+            let mut images = Vec::with_capacity(page.images.len());
+            for img in page.images {
+                let bbox = img.bbox();
+                let Some(id) = img.id else {
+                    if img.image_base64.is_some() {
+                        warn!("Image without id on page {} skipped", page.index + 1);
+                    }
+                    continue;
+                };
+                let header_mime = img
+                    .image_base64
+                    .as_deref()
+                    .and_then(|b64| b64.strip_prefix("data:"))
+                    .and_then(|rest| rest.split_once(';'))
+                    .map(|(mime, _)| mime.to_string());
+                let mime_type = header_mime.unwrap_or_else(|| {
+                    let ext = Path::new(&id)
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_lowercase())
+                        .unwrap_or_else(|| "jpeg".to_string());
+                    mime_for_ext(&ext).to_string()
+                });
+                let data = img
+                    .image_base64
+                    .as_deref()
+                    .map(|b64| decode_image_base64(b64, &id))
+                    .transpose()?;
+                images.push(DocumentImage {
+                    id,
+                    mime_type,
+                    data,
+                    bbox,
+                    annotation: img.image_annotation.map(parse_annotation),
+                });
+            }
+            pages.push(DocumentPage {
+                index: page.index,
+                markdown: page.markdown,
+                images,
+                dimensions: page.dimensions,
+                header: page.header,
+                footer: page.footer,
+            });
+        }
+        Ok(Self {
+            source_name,
+            source_sha256: None,
+            model,
+            usage_info,
+            document_annotation,
+            pages,
+        })
+    }
+
+    /// All images across all pages, in page order.
+    pub fn images(&self) -> impl Iterator<Item = &DocumentImage> {
+        self.pages.iter().flat_map(|p| p.images.iter())
+    }
+
+    /// Concatenated markdown of all pages with image links left pointing at image ids.
+    ///
+    /// With `page_headers`, multi-page documents get a `# Page N` header before each page.
+    pub fn markdown(&self, page_headers: bool) -> String {
+        let multi_page = self.pages.len() > 1;
+        let mut output = String::new();
+        for page in &self.pages {
+            if multi_page && page_headers {
+                output.push_str(&format!("# Page {}\n\n", page.index + 1));
+            }
+            output.push_str(page.markdown.trim_end());
+            output.push_str("\n\n");
+        }
+        output
+    }
+}
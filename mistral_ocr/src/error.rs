@@ -0,0 +1,160 @@
+use std::io;
+use std::time::Duration;
+
+/// Errors returned by the library.
+///
+/// Variants are coarse enough to route on: [`OcrError::is_retryable`] tells transient
+/// API failures apart from inputs that will never succeed.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum OcrError {
+    #[error(
+        "File is {:.1} MB, which exceeds the Mistral OCR upload limit of {} MB",
+        *.size as f64 / (1024.0 * 1024.0),
+        .limit / (1024 * 1024)
+    )]
+    FileTooLarge { size: u64, limit: u64 },
+
+    /// The input is neither a PDF, a supported image, nor a convertible document.
+    /// Holds the offending extension (e.g. `.tar`) or MIME type.
+    #[error(
+        "Unsupported file type: {0} (expected pdf, image, or document: docx, odt, pptx, xlsx, etc.)"
+    )]
+    UnsupportedType(String),
+
+    #[error("Invalid page selection {0}")]
+    InvalidPages(String),
+
+    #[error(
+        "LibreOffice not found. Install it from https://www.libreoffice.org/\n\
+         LibreOffice is only needed for office document conversion (docx, odt, pptx, etc.).\n\
+         PDF and image files work without it."
+    )]
+    ConverterMissing,
+
+    #[error("libreoffice conversion failed: {0}")]
+    Conversion(String),
+
+    /// The API answered with a non-success status other than 429.
+    #[error("OCR request failed (HTTP {status}): {body}")]
+    Api { status: u16, body: String },
+
+    /// The API kept answering HTTP 429 after all retries were used up.
+    #[error("OCR request was rate limited (HTTP 429): {body}")]
+    RateLimited {
+        /// Value of the `Retry-After` header, when the API sent one.
+        retry_after: Option<Duration>,
+        body: String,
+    },
+
+    /// A batch job failed as a whole, or has no result for one of its documents.
+    #[error("Batch job: {0}")]
+    Batch(String),
+
+    /// Network-level failure: connection, timeout, TLS.
+    #[error("OCR request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The API response or an embedded image could not be decoded.
+    #[error("{context}: {source}")]
+    Decode {
+        context: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to write zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Failed to write spreadsheet: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+}
+
+pub type Result<T, E = OcrError> = std::result::Result<T, E>;
+
+impl OcrError {
+    /// Whether the same request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            OcrError::RateLimited { .. } => true,
+            OcrError::Api { status, .. } => *status >= 500,
+            OcrError::Http(err) => err.is_timeout() || err.is_connect(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn decode(
+        context: impl Into<String>,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        OcrError::Decode {
+            context: context.into(),
+            source: Box::new(source),
+        }
+    }
+}
+
+/// `anyhow`-style context for I/O results.
+pub(crate) trait IoContext<T> {
+    fn io_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn io_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|source| OcrError::Io {
+            context: context().into(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_too_large_message() {
+        let err = OcrError::FileTooLarge {
+            size: 60 * 1024 * 1024,
+            limit: 50 * 1024 * 1024,
+        };
+        assert_eq!(
+            err.to_string(),
+            "File is 60.0 MB, which exceeds the Mistral OCR upload limit of 50 MB"
+        );
+    }
+
+    #[test]
+    fn retryable_classification() {
+        assert!(
+            OcrError::RateLimited {
+                retry_after: None,
+                body: String::new()
+            }
+            .is_retryable()
+        );
+        assert!(
+            OcrError::Api {
+                status: 502,
+                body: String::new()
+            }
+            .is_retryable()
+        );
+        assert!(
+            !OcrError::Api {
+                status: 400,
+                body: String::new()
+            }
+            .is_retryable()
+        );
+        assert!(!OcrError::UnsupportedType(".tar".to_string()).is_retryable());
+        assert!(!OcrError::ConverterMissing.is_retryable());
+    }
+}
@@ -0,0 +1,306 @@
+use reqwest::blocking::multipart::{Form, Part};
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::sync::Arc;
+
+use crate::client::parse_json;
+use crate::error::IoContext;
+use crate::{OcrClient, Result};
+
+/// How a document is handed to the OCR endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UploadMode {
+    /// Embed the document in the request as a base64 data URI.
+    #[default]
+    Inline,
+    /// Upload through the Files API and reference it by a signed URL. Avoids the ~33%
+    /// base64 overhead and keeps large request bodies out of retries.
+    Files {
+        /// Delete the uploaded file once the OCR request has finished.
+        delete_after: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum FilePurpose {
+    Ocr,
+    Batch,
+}
+
+impl FilePurpose {
+    fn as_str(self) -> &'static str {
+        match self {
+            FilePurpose::Ocr => "ocr",
+            FilePurpose::Batch => "batch",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UploadedFile {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct SignedUrl {
+    url: String,
+}
+
+/// Reads a shared file from its start, so that every attempt at an upload sends all of it.
+struct FromStart {
+    file: Arc<fs::File>,
+    rewound: bool,
+}
+
+impl Read for FromStart {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut file = &*self.file;
+        if !self.rewound {
+            file.rewind()?;
+            self.rewound = true;
+        }
+        file.read(buf)
+    }
+}
+
+/// How long signed URLs stay valid, in hours. Only needs to outlive the OCR request.
+const SIGNED_URL_EXPIRY_HOURS: u32 = 1;
+
+impl OcrClient {
+    /// Upload `data` to the Files API and return the file id.
+    pub(crate) fn upload_file(
+        &self,
+        name: &str,
+        mime_type: &'static str,
+        data: &[u8],
+        purpose: FilePurpose,
+    ) -> Result<String> {
+        self.upload(name, mime_type, purpose, || Part::bytes(data.to_vec()))
+    }
+
+    /// Upload the contents of `file` to the Files API without reading it into memory,
+    /// and return the file id.
+    pub(crate) fn upload_from_file(
+        &self,
+        name: &str,
+        mime_type: &'static str,
+        file: fs::File,
+        purpose: FilePurpose,
+    ) -> Result<String> {
+        let len = file
+            .metadata()
+            .io_context(|| format!("Failed to read {name}"))?
+            .len();
+        let file = Arc::new(file);
+        self.upload(name, mime_type, purpose, || {
+            let reader = FromStart {
+                file: Arc::clone(&file),
+                rewound: false,
+            };
+            Part::reader_with_length(reader, len)
+        })
+    }
+
+    fn upload(
+        &self,
+        name: &str,
+        mime_type: &'static str,
+        purpose: FilePurpose,
+        body: impl Fn() -> Part,
+    ) -> Result<String> {
+        let response = self.execute("File upload", || {
+            let file = body()
+                .file_name(name.to_string())
+                .mime_str(mime_type)
+                .expect("supported MIME types are valid");
+            let form = Form::new()
+                .text("purpose", purpose.as_str())
+                .part("file", file);
+            self.http
+                .post(self.config.url("/files"))
+                .bearer_auth(&self.config.api_key)
+                .multipart(form)
+        })?;
+        let file: UploadedFile = parse_json(&response.text()?, "Failed to parse upload response")?;
+        Ok(file.id)
+    }
+
+    /// Temporary download URL for an uploaded file, for use as a `document_url`.
+    pub(crate) fn signed_url(&self, file_id: &str) -> Result<String> {
+        let response = self.execute("Signed URL request", || {
+            self.http
+                .get(self.config.url(&format!(
+                    "/files/{file_id}/url?expiry={SIGNED_URL_EXPIRY_HOURS}"
+                )))
+                .bearer_auth(&self.config.api_key)
+        })?;
+        let signed: SignedUrl = parse_json(&response.text()?, "Failed to parse signed URL")?;
+        Ok(signed.url)
+    }
+
+    /// Contents of a file such as a batch job's output, as text.
+    pub(crate) fn file_content(&self, file_id: &str) -> Result<String> {
+        let response = self.execute("File download", || {
+            self.http
+                .get(self.config.url(&format!("/files/{file_id}/content")))
+                .bearer_auth(&self.config.api_key)
+        })?;
+        Ok(response.text()?)
+    }
+
+    pub(crate) fn delete_file(&self, file_id: &str) -> Result<()> {
+        self.execute("File deletion", || {
+            self.http
+                .delete(self.config.url(&format!("/files/{file_id}")))
+                .bearer_auth(&self.config.api_key)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl crate::AsyncOcrClient {
+    pub(crate) async fn upload_file(
+        &self,
+        name: &str,
+        mime_type: &'static str,
+        data: &[u8],
+        purpose: FilePurpose,
+    ) -> Result<String> {
+        use reqwest::multipart::{Form, Part};
+
+        let response = self
+            .execute("File upload", || {
+                let file = Part::bytes(data.to_vec())
+                    .file_name(name.to_string())
+                    .mime_str(mime_type)
+                    .expect("supported MIME types are valid");
+                let form = Form::new()
+                    .text("purpose", purpose.as_str())
+                    .part("file", file);
+                self.http
+                    .post(self.config.url("/files"))
+                    .bearer_auth(&self.config.api_key)
+                    .multipart(form)
+            })
+            .await?;
+        let file: UploadedFile =
+            parse_json(&response.text().await?, "Failed to parse upload response")?;
+        Ok(file.id)
+    }
+
+    pub(crate) async fn signed_url(&self, file_id: &str) -> Result<String> {
+        let response = self
+            .execute("Signed URL request", || {
+                self.http
+                    .get(self.config.url(&format!(
+                        "/files/{file_id}/url?expiry={SIGNED_URL_EXPIRY_HOURS}"
+                    )))
+                    .bearer_auth(&self.config.api_key)
+            })
+            .await?;
+        let signed: SignedUrl = parse_json(&response.text().await?, "Failed to parse signed URL")?;
+        Ok(signed.url)
+    }
+
+    pub(crate) async fn delete_file(&self, file_id: &str) -> Result<()> {
+        self.execute("File deletion", || {
+            self.http
+                .delete(self.config.url(&format!("/files/{file_id}")))
+                .bearer_auth(&self.config.api_key)
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OcrInput, OcrOptions};
+    use mockito::Matcher;
+
+    fn mock_ocr_body() -> String {
+        serde_json::json!({
+            "pages": [{"index": 0, "markdown": "uploaded page", "images": []}]
+        })
+        .to_string()
+    }
+
+    fn files_client(server: &mockito::Server, delete_after: bool) -> OcrClient {
+        OcrClient::builder("test-key")
+            .base_url(server.url())
+            .upload_mode(UploadMode::Files { delete_after })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn document_is_uploaded_and_referenced_by_signed_url() {
+        let mut server = mockito::Server::new();
+        let signed = format!("{}/signed/report.pdf", server.url());
+        let upload = server
+            .mock("POST", "/files")
+            .match_header("authorization", "Bearer test-key")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#"name="purpose"\r\n\r\nocr"#.to_string()),
+                Matcher::Regex(r#"filename="report.pdf""#.to_string()),
+                Matcher::Regex("%PDF-1.4".to_string()),
+            ]))
+            .with_body(r#"{"id": "file-123", "object": "file"}"#)
+            .create();
+        let url = server
+            .mock("GET", "/files/file-123/url")
+            .match_query(Matcher::UrlEncoded("expiry".into(), "1".into()))
+            .with_body(serde_json::json!({ "url": signed }).to_string())
+            .create();
+        let ocr = server
+            .mock("POST", "/ocr")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "document": {"type": "document_url", "document_url": signed}
+            })))
+            .with_body(mock_ocr_body())
+            .create();
+        let delete = server
+            .mock("DELETE", "/files/file-123")
+            .with_body(r#"{"id": "file-123", "deleted": true}"#)
+            .create();
+
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("report.pdf");
+        let document = files_client(&server, true)
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+
+        assert_eq!(document.pages[0].markdown, "uploaded page");
+        upload.assert();
+        url.assert();
+        ocr.assert();
+        delete.assert();
+    }
+
+    #[test]
+    fn uploads_are_kept_unless_deletion_is_requested() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/files")
+            .with_body(r#"{"id": "file-456"}"#)
+            .create();
+        server
+            .mock("GET", "/files/file-456/url")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"url": "https://example.com/signed"}"#)
+            .create();
+        server
+            .mock("POST", "/ocr")
+            .with_body(mock_ocr_body())
+            .create();
+        let delete = server.mock("DELETE", "/files/file-456").expect(0).create();
+
+        let input = OcrInput::from_bytes(&b"png"[..]).with_mime_type("image/png");
+        files_client(&server, false)
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        delete.assert();
+    }
+}
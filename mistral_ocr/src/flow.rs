@@ -0,0 +1,190 @@
+/// Concatenate pages of markdown into one continuous text, each given with the marker
+/// to place where it starts, if any.
+///
+/// A page that ends mid-sentence is joined to the next one: its last line has no closing
+/// punctuation and the next page starts with a paragraph in lower case, or with any
+/// paragraph if the page ends on a comma. The marker then goes inline at the join, before
+/// the next page's first word, or after it when that word completes one hyphenated at the
+/// break; otherwise it goes on a line of its own before the page. Words hyphenated at line
+/// ends within a paragraph are rejoined as well.
+pub(crate) fn join_pages(pages: Vec<(Option<String>, String)>) -> String {
+    let mut output = Output::default();
+    for (marker, md) in pages {
+        let md = dehyphenate(md.trim());
+        if md.is_empty() && marker.is_none() {
+            continue;
+        }
+        let first_line = md.lines().next().unwrap_or("");
+        if !output.text.is_empty() && continues(output.last_line(), first_line) {
+            if ends_hyphenated(output.last_line()) {
+                let (word, rest) = first_line_split(&md);
+                output.text.pop();
+                output.push(word);
+                output.push(marker.as_deref().unwrap_or(""));
+                output.push(rest);
+            } else {
+                output.push(" ");
+                output.push(marker.as_deref().unwrap_or(""));
+                output.push(&md);
+            }
+            continue;
+        }
+        if !output.text.is_empty() {
+            output.push("\n\n");
+        }
+        if let Some(marker) = marker {
+            output.push(&marker);
+            if !md.is_empty() {
+                output.push("\n\n");
+            }
+        }
+        output.push(&md);
+    }
+    output.text.push('\n');
+    output.text
+}
+
+/// Text being joined, keeping track of where its last line starts.
+#[derive(Default)]
+struct Output {
+    text: String,
+    last_line: usize,
+}
+
+impl Output {
+    fn push(&mut self, s: &str) {
+        if let Some(i) = s.rfind('\n') {
+            self.last_line = self.text.len() + i + 1;
+        }
+        self.text.push_str(s);
+    }
+
+    fn last_line(&self) -> &str {
+        &self.text[self.last_line..]
+    }
+}
+
+/// The first word of `md` and everything after it.
+fn first_line_split(md: &str) -> (&str, &str) {
+    let end = md.find(char::is_whitespace).unwrap_or(md.len());
+    md.split_at(end)
+}
+
+/// Rejoin words hyphenated at line ends within paragraphs, leaving code blocks alone.
+fn dehyphenate(md: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code = false;
+    for line in md.lines() {
+        let fence = line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~");
+        if fence {
+            in_code = !in_code;
+        }
+        match lines.last_mut() {
+            Some(prev) if !in_code && !fence && ends_hyphenated(prev) && continues(prev, line) => {
+                prev.truncate(prev.trim_end().len() - 1);
+                prev.push_str(line.trim_start());
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Whether paragraph text ending with `line` carries on with `next`.
+fn continues(line: &str, next: &str) -> bool {
+    let line = line.trim_end();
+    if !can_continue(line) || !is_paragraph_text(next) || ends_sentence(line) {
+        return false;
+    }
+    let lower_case = next.trim_start().starts_with(char::is_lowercase);
+    if ends_hyphenated(line) {
+        return lower_case;
+    }
+    lower_case || line.ends_with(',')
+}
+
+/// Whether `line` is running text that a following line may continue, including a list
+/// item.
+fn can_continue(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty()
+        && !["#", "|", "![", "<", "```", "~~~", "$$"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+/// Whether `line` is running text starting a paragraph, rather than a heading, table,
+/// image, list item, quote, HTML or math block.
+fn is_paragraph_text(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if !can_continue(trimmed) || trimmed.starts_with('>') {
+        return false;
+    }
+    let list_item = ["- ", "* ", "+ "].iter().any(|m| trimmed.starts_with(m))
+        || trimmed
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    !list_item
+}
+
+fn ends_sentence(line: &str) -> bool {
+    let text = line.trim_end_matches(['"', '\'', '”', '’', ')', ']', '*', '_']);
+    text.ends_with(['.', '!', '?', ':', '…'])
+}
+
+/// Whether `line` ends with a word broken by a hyphen, such as `infor-`.
+fn ends_hyphenated(line: &str) -> bool {
+    let mut chars = line.trim_end().chars().rev();
+    chars.next() == Some('-') && chars.next().is_some_and(char::is_alphabetic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(n: u32) -> Option<String> {
+        Some(format!("<a id=\"page-{n}\"></a>"))
+    }
+
+    #[test]
+    fn sentences_and_words_are_rejoined_across_pages() {
+        let pages = vec![
+            (
+                marker(1),
+                "# Report\n\nThe results of the survey were\n".to_string(),
+            ),
+            (
+                marker(2),
+                "broadly positive, with most infor-\nmation confirmed by the audit-".to_string(),
+            ),
+            (marker(3), "ors in March.\n\n- item".to_string()),
+            (marker(4), "Summary\n\nA new section.".to_string()),
+        ];
+        assert_eq!(
+            join_pages(pages),
+            "<a id=\"page-1\"></a>\n\n# Report\n\n\
+             The results of the survey were <a id=\"page-2\"></a>broadly positive, with most \
+             information confirmed by the auditors<a id=\"page-3\"></a> in March.\n\n- item\n\n\
+             <a id=\"page-4\"></a>\n\nSummary\n\nA new section.\n"
+        );
+    }
+
+    #[test]
+    fn structure_breaks_are_kept() {
+        let pages = vec![
+            (None, "Text without a full stop".to_string()),
+            (None, "# Heading".to_string()),
+            (None, "Ends a sentence.".to_string()),
+            (None, "lower case, but a new sentence before".to_string()),
+            (None, "| a | b |".to_string()),
+        ];
+        assert_eq!(
+            join_pages(pages),
+            "Text without a full stop\n\n# Heading\n\nEnds a sentence.\n\n\
+             lower case, but a new sentence before\n\n| a | b |\n"
+        );
+        assert_eq!(dehyphenate("```\nx = a-\nb\n```"), "```\nx = a-\nb\n```");
+        assert_eq!(dehyphenate("well-\nKnown"), "well-\nKnown");
+        assert_eq!(dehyphenate("a re-\nmark-\nable one"), "a remarkable one");
+    }
+}
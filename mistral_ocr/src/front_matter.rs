@@ -0,0 +1,116 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::OcrDocument;
+
+/// YAML front matter describing `document`, including the `---` delimiters.
+///
+/// `page` is set for per-page files. Strings are written as double-quoted JSON
+/// strings, which are valid YAML whatever characters a file name contains.
+pub(crate) fn front_matter(document: &OcrDocument, page: Option<u32>, now: SystemTime) -> String {
+    let quote = |s: &str| serde_json::Value::from(s).to_string();
+    let mut yaml = String::from("---\n");
+    if let Some(name) = &document.source_name {
+        yaml.push_str(&format!("source: {}\n", quote(name)));
+    }
+    if let Some(sha256) = &document.source_sha256 {
+        yaml.push_str(&format!("sha256: {sha256}\n"));
+    }
+    if let Some(page) = page {
+        yaml.push_str(&format!("page: {}\n", page + 1));
+    }
+    yaml.push_str(&format!("pages: {}\n", document.pages.len()));
+    if !document.model.is_empty() {
+        yaml.push_str(&format!("model: {}\n", quote(&document.model)));
+    }
+    yaml.push_str(&format!("processed: {}\n", rfc3339(now)));
+    yaml.push_str(&format!(
+        "tool: {}\n",
+        quote(concat!("mistral_ocr ", env!("CARGO_PKG_VERSION")))
+    ));
+    if let Some(usage) = &document.usage_info {
+        yaml.push_str("usage:\n");
+        yaml.push_str(&format!("  pages_processed: {}\n", usage.pages_processed));
+        if let Some(size) = usage.doc_size_bytes {
+            yaml.push_str(&format!("  doc_size_bytes: {size}\n"));
+        }
+    }
+    yaml.push_str("---\n\n");
+    yaml
+}
+
+/// UTC timestamp such as `2024-05-01T12:30:00Z`.
+fn rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DocumentPage, UsageInfo};
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_are_utc_rfc3339() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(rfc3339(leap_day), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn front_matter_lists_document_metadata() {
+        let document = OcrDocument {
+            source_name: Some("Q3: \"final\".pdf".to_string()),
+            source_sha256: Some("ab12".to_string()),
+            model: "mistral-ocr-2505".to_string(),
+            usage_info: Some(UsageInfo {
+                pages_processed: 2,
+                doc_size_bytes: Some(1024),
+            }),
+            document_annotation: None,
+            pages: vec![DocumentPage {
+                index: 0,
+                markdown: String::new(),
+                images: vec![],
+                dimensions: None,
+                header: None,
+                footer: None,
+            }],
+        };
+        let yaml = front_matter(&document, None, UNIX_EPOCH);
+        assert_eq!(
+            yaml,
+            format!(
+                "---\n\
+                 source: \"Q3: \\\"final\\\".pdf\"\n\
+                 sha256: ab12\n\
+                 pages: 1\n\
+                 model: \"mistral-ocr-2505\"\n\
+                 processed: 1970-01-01T00:00:00Z\n\
+                 tool: \"mistral_ocr {}\"\n\
+                 usage:\n  pages_processed: 2\n  doc_size_bytes: 1024\n\
+                 ---\n\n",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    DocumentPage, ImageWriter, OcrDocument, OcrOptions, Result, create_parent_dir, output_stem,
+};
+
+/// Loaded only for documents that contain math, unless [`OcrOptions::mathjax`] is off;
+/// without network access the TeX source stays readable between its `\(`/`\[` delimiters.
+const MATHJAX: &str = r#"<script id="MathJax-script" async src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js"></script>"#;
+
+const STYLE: &str = "\
+body { max-width: 50em; margin: 2em auto; padding: 0 1em; font-family: sans-serif; line-height: 1.5; }
+img { max-width: 100%; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; }
+section.page + section.page { border-top: 1px solid #ccc; margin-top: 2em; }
+.page-header { color: #888; font-size: 0.9em; }
+.page-header a { color: inherit; }";
+
+/// Render an OCR result as a standalone HTML page at `output_path`.
+///
+/// Each page becomes a `<section id="page-N">` so pages can be linked to; with
+/// `options.page_headers`, multi-page documents show a linked "Page N" label above each.
+/// Images are handled per `options.image_mode` as for [`write_markdown`](crate::write_markdown);
+/// where the page size and image bounding box are known, images are scaled to the share of
+/// the page width they took up and carry their box as `data-bbox="x0,y0,x1,y1"`. Math is
+/// passed through as TeX for MathJax to render, which is loaded from a CDN if
+/// `options.mathjax` is set, so it needs network access.
+pub fn write_html(output_path: &Path, document: &OcrDocument, options: &OcrOptions) -> Result<()> {
+    create_parent_dir(output_path)?;
+    let mut images = ImageWriter::new(output_path, options.image_mode);
+    let title = document
+        .source_name
+        .clone()
+        .unwrap_or_else(|| output_stem(output_path));
+
+    let mut body = String::new();
+    let mut has_math = false;
+    let multi_page = document.pages.len() > 1;
+    for page in &document.pages {
+        let number = page.index + 1;
+        body.push_str(&format!("<section class=\"page\" id=\"page-{number}\""));
+        if let Some(dims) = page.dimensions {
+            body.push_str(&format!(
+                " data-width=\"{}\" data-height=\"{}\" data-dpi=\"{}\"",
+                dims.width, dims.height, dims.dpi
+            ));
+        }
+        body.push_str(">\n");
+        if multi_page && options.page_headers {
+            body.push_str(&format!(
+                "<p class=\"page-header\"><a href=\"#page-{number}\">Page {number}</a></p>\n"
+            ));
+        }
+        let links = images.page_links(page)?;
+        let events = page_events(page, &links, &mut has_math);
+        pulldown_cmark::html::push_html(&mut body, events.into_iter());
+        body.push_str("</section>\n");
+    }
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}\n</style>\n",
+        escape_html(&title)
+    );
+    if has_math && options.mathjax {
+        html.push_str(MATHJAX);
+        html.push('\n');
+    }
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&body);
+    html.push_str("</body>\n</html>\n");
+
+    images.finish(output_path, "html", html.as_bytes())
+}
+
+/// Markdown events of `page`, with images rendered as `<img>` tags pointing at `links`
+/// and math as TeX. Raw HTML in the markdown is shown as text, except for `<br>` line
+/// breaks, so that markup in a scanned page cannot run as part of the output.
+fn page_events<'a>(
+    page: &'a DocumentPage,
+    links: &'a HashMap<String, String>,
+    has_math: &mut bool,
+) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    // Source, title and alt text of the image being read.
+    let mut image: Option<(CowStr, CowStr, String)> = None;
+    let parser = Parser::new_ext(
+        page.markdown.trim_end(),
+        Options::ENABLE_TABLES | Options::ENABLE_MATH,
+    );
+    for event in parser {
+        match (event, &mut image) {
+            (
+                Event::Start(Tag::Image {
+                    dest_url, title, ..
+                }),
+                None,
+            ) => image = Some((dest_url, title, String::new())),
+            (Event::End(TagEnd::Image), Some(_)) => {
+                let (src, title, alt) = image.take().unwrap();
+                events.push(Event::InlineHtml(
+                    img_tag(page, links, &src, &title, &alt).into(),
+                ));
+            }
+            (Event::Text(text) | Event::Code(text), Some((_, _, alt))) => alt.push_str(&text),
+            (_, Some(_)) => {}
+            (Event::Html(html) | Event::InlineHtml(html), None) if is_line_break(&html) => {
+                events.push(Event::HardBreak)
+            }
+            (Event::Html(html) | Event::InlineHtml(html), None) => events.push(Event::Text(html)),
+            (event, None) => events.extend(math_to_tex(event, has_math)),
+        }
+    }
+    events
+}
+
+/// `<img>` tag for the image with id `src` on `page`.
+fn img_tag(
+    page: &DocumentPage,
+    links: &HashMap<String, String>,
+    src: &str,
+    title: &str,
+    alt: &str,
+) -> String {
+    let link = links.get(src).map_or(src, String::as_str);
+    let mut tag = format!(
+        "<img src=\"{}\" alt=\"{}\"",
+        escape_html(link),
+        escape_html(alt)
+    );
+    if !title.is_empty() {
+        tag.push_str(&format!(" title=\"{}\"", escape_html(title)));
+    }
+    let bbox = page
+        .images
+        .iter()
+        .find(|img| img.id == src)
+        .and_then(|img| img.bbox);
+    if let Some(bbox) = bbox {
+        if let Some(dims) = page.dimensions.filter(|d| d.width > 0) {
+            let percent = f64::from(bbox.width()) * 100.0 / f64::from(dims.width);
+            tag.push_str(&format!(" style=\"width: {percent:.1}%\""));
+        }
+        tag.push_str(&format!(
+            " data-bbox=\"{},{},{},{}\"",
+            bbox.top_left_x, bbox.top_left_y, bbox.bottom_right_x, bbox.bottom_right_y
+        ));
+    }
+    tag.push_str(" />");
+    tag
+}
+
+/// Replace math events with their TeX source in MathJax delimiters, wrapped in a span
+/// that marks them as math.
+fn math_to_tex<'a>(event: Event<'a>, has_math: &mut bool) -> Vec<Event<'a>> {
+    let (class, open, close, tex) = match event {
+        Event::InlineMath(tex) => ("math-inline", "\\(", "\\)", tex),
+        Event::DisplayMath(tex) => ("math-display", "\\[", "\\]", tex),
+        event => return vec![event],
+    };
+    *has_math = true;
+    vec![
+        Event::InlineHtml(CowStr::from(format!("<span class=\"math {class}\">"))),
+        Event::Text(CowStr::from(format!("{open}{tex}{close}"))),
+        Event::InlineHtml(CowStr::from("</span>")),
+    ]
+}
+
+/// Whether `html` is a `<br>` tag, which OCR output uses for line breaks within table
+/// cells and paragraphs.
+pub(crate) fn is_line_break(html: &str) -> bool {
+    let tag = html.trim().to_ascii_lowercase();
+    matches!(tag.as_str(), "<br>" | "<br/>" | "<br />")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, DocumentImage, ImageMode, PageDimensions};
+    use std::fs;
+
+    #[test]
+    fn pages_become_sections_with_images_and_math() {
+        let document = OcrDocument {
+            source_name: Some("a <b>.pdf".to_string()),
+            source_sha256: None,
+            model: "m".to_string(),
+            usage_info: None,
+            document_annotation: None,
+            pages: vec![
+                DocumentPage {
+                    index: 0,
+                    markdown: "# Title\n\nEnergy $E = mc^2$\n\n![img-0.png](img-0.png)".into(),
+                    images: vec![DocumentImage {
+                        id: "img-0.png".to_string(),
+                        mime_type: "image/png".to_string(),
+                        data: Some(b"png".to_vec()),
+                        bbox: Some(BoundingBox {
+                            top_left_x: 100,
+                            top_left_y: 100,
+                            bottom_right_x: 350,
+                            bottom_right_y: 300,
+                        }),
+                        annotation: None,
+                    }],
+                    dimensions: Some(PageDimensions {
+                        dpi: 200,
+                        height: 1400,
+                        width: 1000,
+                    }),
+                    header: None,
+                    footer: None,
+                },
+                DocumentPage {
+                    index: 1,
+                    markdown: "| a | b |\n|---|---|\n| 1 | 2<br>3 |\n\n\
+                               <script>alert(1)</script>\n\nA <img src=x onerror=alert(2)> tag"
+                        .into(),
+                    images: vec![],
+                    dimensions: None,
+                    header: None,
+                    footer: None,
+                },
+            ],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.html");
+        let options = OcrOptions {
+            image_mode: ImageMode::Separate,
+            ..Default::default()
+        };
+        write_html(&out, &document, &options).unwrap();
+
+        let html = fs::read_to_string(&out).unwrap();
+        assert!(html.contains("<title>a &lt;b&gt;.pdf</title>"));
+        assert!(html.contains(
+            r#"<section class="page" id="page-1" data-width="1000" data-height="1400" data-dpi="200">"#
+        ));
+        assert!(html.contains("<section class=\"page\" id=\"page-2\">"));
+        assert!(html.contains("<a href=\"#page-2\">Page 2</a>"));
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains(r#"<span class="math math-inline">\(E = mc^2\)</span>"#));
+        assert!(html.contains("MathJax"));
+        assert!(html.contains(
+            r#"<img src="doc_images/img-0.png" alt="img-0.png" style="width: 25.0%" data-bbox="100,100,350,300" />"#
+        ));
+        assert!(html.contains("<td>2<br />\n3</td>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("A &lt;img src=x onerror=alert(2)&gt; tag"));
+        assert!(!html.contains("<script>"));
+        assert_eq!(
+            fs::read(dir.path().join("doc_images/img-0.png")).unwrap(),
+            b"png"
+        );
+
+        let options = OcrOptions {
+            mathjax: false,
+            ..Default::default()
+        };
+        write_html(&out, &document, &options).unwrap();
+        let html = fs::read_to_string(&out).unwrap();
+        assert!(html.contains(r#"\(E = mc^2\)"#));
+        assert!(!html.contains("<script"));
+    }
+}
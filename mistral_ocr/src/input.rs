@@ -0,0 +1,408 @@
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+
+use crate::cache::to_hex;
+use crate::error::IoContext;
+use crate::{CONVERTIBLE_EXTENSIONS, IMAGE_EXTENSIONS, OcrError, Result, mime_for_ext};
+
+/// A document to OCR: a file on disk, bytes already held in memory, or a remote URL.
+///
+/// The document type is taken from the declared MIME type when one is set, and from
+/// the file name extension otherwise.
+///
+/// ```
+/// use mistral_ocr::OcrInput;
+///
+/// let upload: &[u8] = b"%PDF-1.7 ...";
+/// let input = OcrInput::from_bytes(upload)
+///     .with_mime_type("application/pdf")
+///     .with_file_name("upload.pdf");
+/// assert_eq!(input.file_name(), Some("upload.pdf"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct OcrInput<'a> {
+    source: Source<'a>,
+    file_name: Option<String>,
+    mime_type: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+enum Source<'a> {
+    File(PathBuf),
+    Bytes(Cow<'a, [u8]>),
+    Url(String),
+}
+
+/// What gets sent to the API once office documents have been converted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InputKind {
+    Pdf,
+    Image(&'static str),
+    Convertible(&'static str),
+}
+
+impl<'a> OcrInput<'a> {
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+        Self {
+            source: Source::File(path),
+            file_name,
+            mime_type: None,
+        }
+    }
+
+    pub fn from_bytes(data: impl Into<Cow<'a, [u8]>>) -> Self {
+        Self {
+            source: Source::Bytes(data.into()),
+            file_name: None,
+            mime_type: None,
+        }
+    }
+
+    /// Document at a public `http(s)` URL, which the API fetches itself.
+    ///
+    /// Nothing is downloaded or converted locally, so splitting and office conversion
+    /// do not apply; URLs ending in an image extension are sent as images, anything
+    /// else as a document. Use [`OcrClient::download`](crate::OcrClient::download) for
+    /// URLs the API cannot reach.
+    pub fn from_url(url: impl Into<String>) -> Self {
+        let url = url.into();
+        let file_name = url_file_name(&url);
+        Self {
+            source: Source::Url(url),
+            file_name,
+            mime_type: None,
+        }
+    }
+
+    /// Read the whole document from `reader` into memory.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self> {
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .io_context(|| "Failed to read input document")?;
+        Ok(Self::from_bytes(data))
+    }
+
+    /// File name reported to the API and used to detect the type when no MIME type is set.
+    pub fn with_file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Declared MIME type, e.g. from an HTTP `Content-Type` header.
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    /// The remote URL, for inputs created with [`OcrInput::from_url`].
+    pub fn url(&self) -> Option<&str> {
+        match &self.source {
+            Source::Url(url) => Some(url),
+            Source::File(_) | Source::Bytes(_) => None,
+        }
+    }
+
+    /// How a remote URL is referenced in the request. Unlike local inputs, URLs without
+    /// a recognisable extension (e.g. `/pdf/2401.00001`) are assumed to be documents.
+    pub(crate) fn remote_kind(&self) -> InputKind {
+        match self.kind() {
+            Ok(InputKind::Image(mime)) => InputKind::Image(mime),
+            _ => InputKind::Pdf,
+        }
+    }
+
+    pub(crate) fn kind(&self) -> Result<InputKind> {
+        if let Some(mime) = &self.mime_type {
+            return kind_for_mime(mime)
+                .ok_or_else(|| OcrError::UnsupportedType(format!("MIME type {mime}")));
+        }
+        let ext = self
+            .file_name
+            .as_deref()
+            .and_then(|n| Path::new(n).extension())
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        kind_for_ext(&ext).ok_or_else(|| OcrError::UnsupportedType(format!(".{ext}")))
+    }
+
+    /// Hex SHA-256 of the document as given, before any conversion; `None` for URLs.
+    pub(crate) fn sha256(&self) -> Result<Option<String>> {
+        let mut hasher = Sha256::new();
+        match &self.source {
+            Source::File(path) => {
+                let mut file = fs::File::open(path)
+                    .io_context(|| format!("File not found: {}", path.display()))?;
+                io::copy(&mut file, &mut hasher)
+                    .io_context(|| format!("Failed to read {}", path.display()))?;
+            }
+            Source::Bytes(data) => hasher.update(data),
+            Source::Url(_) => return Ok(None),
+        }
+        Ok(Some(to_hex(&hasher.finalize())))
+    }
+
+    /// Document bytes ready to send, converting office documents to PDF first.
+    pub(crate) fn prepare(&self) -> Result<(InputKind, Cow<'_, [u8]>)> {
+        let kind = self.kind()?;
+        let InputKind::Convertible(ext) = kind else {
+            let data = match &self.source {
+                Source::File(path) => Cow::Owned(
+                    fs::read(path).io_context(|| format!("File not found: {}", path.display()))?,
+                ),
+                Source::Bytes(data) => Cow::Borrowed(data.as_ref()),
+                Source::Url(_) => unreachable!("remote URLs are passed through, not read"),
+            };
+            return Ok((kind, data));
+        };
+
+        info!("Converting .{ext} to PDF via LibreOffice...");
+        let data = match &self.source {
+            Source::File(path) => convert_to_pdf(path)?,
+            Source::Bytes(data) => {
+                let dir = tempfile::tempdir().io_context(|| "Failed to create temp directory")?;
+                let stem = self
+                    .file_name
+                    .as_deref()
+                    .and_then(|n| Path::new(n).file_stem())
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "document".to_string());
+                let path = dir.path().join(format!("{stem}.{ext}"));
+                fs::write(&path, data).io_context(|| "Failed to write temp input file")?;
+                convert_to_pdf(&path)?
+            }
+            Source::Url(_) => unreachable!("remote URLs are passed through, not converted"),
+        };
+        Ok((InputKind::Pdf, Cow::Owned(data)))
+    }
+}
+
+fn kind_for_ext(ext: &str) -> Option<InputKind> {
+    if ext == "pdf" {
+        Some(InputKind::Pdf)
+    } else if IMAGE_EXTENSIONS.contains(&ext) {
+        Some(InputKind::Image(mime_for_ext(ext)))
+    } else {
+        CONVERTIBLE_EXTENSIONS
+            .iter()
+            .find(|e| **e == ext)
+            .map(|e| InputKind::Convertible(e))
+    }
+}
+
+/// Last path segment of `url`, without query or fragment.
+pub(crate) fn url_file_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let (_, name) = path.rsplit_once('/')?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+pub(crate) fn kind_for_mime(mime: &str) -> Option<InputKind> {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    let ext = match essence.to_ascii_lowercase().as_str() {
+        "application/pdf" => "pdf",
+        "image/jpeg" | "image/jpg" => "jpeg",
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "image/webp" => "webp",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.oasis.opendocument.text" => "odt",
+        "application/rtf" | "text/rtf" => "rtf",
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        "application/vnd.ms-powerpoint" => "ppt",
+        "application/vnd.oasis.opendocument.presentation" => "odp",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.oasis.opendocument.spreadsheet" => "ods",
+        "text/csv" => "csv",
+        "application/epub+zip" => "epub",
+        _ => return None,
+    };
+    kind_for_ext(ext)
+}
+
+fn find_libreoffice() -> Result<PathBuf> {
+    for name in &["libreoffice", "soffice"] {
+        if let Ok(output) = Command::new("which").arg(name).output()
+            && output.status.success()
+        {
+            return Ok(PathBuf::from(name));
+        }
+        if let Ok(output) = Command::new("where").arg(name).output()
+            && output.status.success()
+        {
+            return Ok(PathBuf::from(name));
+        }
+    }
+
+    let candidates: &[&str] = if cfg!(target_os = "macos") {
+        &[
+            "/Applications/LibreOffice.app/Contents/MacOS/soffice",
+            "/opt/homebrew/bin/soffice",
+        ]
+    } else if cfg!(target_os = "windows") {
+        &[
+            r"C:\Program Files\LibreOffice\program\soffice.exe",
+            r"C:\Program Files (x86)\LibreOffice\program\soffice.exe",
+        ]
+    } else {
+        &["/usr/bin/libreoffice", "/usr/bin/soffice"]
+    };
+
+    for path in candidates {
+        if Path::new(path).exists() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+
+    Err(OcrError::ConverterMissing)
+}
+
+/// Convert `input_path` to PDF with LibreOffice and return the PDF bytes.
+///
+/// Every call gets its own output directory and LibreOffice profile, so conversions can
+/// run concurrently, even of inputs with the same file stem.
+fn convert_to_pdf(input_path: &Path) -> Result<Vec<u8>> {
+    let lo_bin = find_libreoffice()?;
+    let temp_dir = tempfile::tempdir().io_context(|| "Failed to create temp directory")?;
+    let profile = file_url(&temp_dir.path().join("profile"))?;
+
+    let output = Command::new(&lo_bin)
+        .arg(format!("-env:UserInstallation={profile}"))
+        .args(["--headless", "--convert-to", "pdf", "--outdir"])
+        .arg(temp_dir.path())
+        .arg(input_path)
+        .output()
+        .io_context(|| format!("Failed to run LibreOffice at {}", lo_bin.display()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(OcrError::Conversion(stderr.into_owned()));
+    }
+
+    let stem = input_path
+        .file_stem()
+        .ok_or_else(|| OcrError::Conversion("input file has no stem".to_string()))?;
+    let pdf_path = temp_dir
+        .path()
+        .join(format!("{}.pdf", stem.to_string_lossy()));
+
+    if !pdf_path.exists() {
+        return Err(OcrError::Conversion(format!(
+            "libreoffice did not produce expected PDF at {}",
+            pdf_path.display()
+        )));
+    }
+
+    fs::read(&pdf_path)
+        .io_context(|| format!("Failed to read converted PDF {}", pdf_path.display()))
+}
+
+/// Percent-encoded `file://` URL of an absolute path, as LibreOffice expects for its
+/// profile location.
+fn file_url(path: &Path) -> Result<String> {
+    reqwest::Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|()| OcrError::Conversion(format!("{} is not an absolute path", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_from_file_name() {
+        let input = OcrInput::from_bytes(&b""[..]).with_file_name("Scan.PDF");
+        assert_eq!(input.kind().unwrap(), InputKind::Pdf);
+        let input = OcrInput::from_bytes(&b""[..]).with_file_name("photo.jpg");
+        assert_eq!(input.kind().unwrap(), InputKind::Image("image/jpeg"));
+        let input = OcrInput::from_path("report.docx");
+        assert_eq!(input.kind().unwrap(), InputKind::Convertible("docx"));
+    }
+
+    #[test]
+    fn declared_mime_type_wins_over_file_name() {
+        let input = OcrInput::from_bytes(&b""[..])
+            .with_file_name("upload.bin")
+            .with_mime_type("image/png; charset=binary");
+        assert_eq!(input.kind().unwrap(), InputKind::Image("image/png"));
+        let input = OcrInput::from_bytes(&b""[..]).with_mime_type(
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        );
+        assert_eq!(input.kind().unwrap(), InputKind::Convertible("docx"));
+    }
+
+    #[test]
+    fn unknown_types_are_rejected() {
+        let err = OcrInput::from_bytes(&b""[..])
+            .with_file_name("archive.tar")
+            .kind()
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsupported file type: .tar"));
+        let err = OcrInput::from_bytes(&b""[..])
+            .with_mime_type("application/x-tar")
+            .kind()
+            .unwrap_err();
+        assert!(matches!(err, OcrError::UnsupportedType(t) if t == "MIME type application/x-tar"));
+        assert!(OcrInput::from_bytes(&b""[..]).kind().is_err());
+    }
+
+    #[test]
+    fn url_input_takes_name_from_path() {
+        let input = OcrInput::from_url("https://example.com/papers/scan.png?token=abc#top");
+        assert_eq!(input.file_name(), Some("scan.png"));
+        assert_eq!(input.remote_kind(), InputKind::Image("image/png"));
+
+        let input = OcrInput::from_url("https://arxiv.org/pdf/2401.00001");
+        assert_eq!(input.file_name(), Some("2401.00001"));
+        assert_eq!(input.remote_kind(), InputKind::Pdf);
+
+        assert_eq!(OcrInput::from_url("https://example.com/").file_name(), None);
+        assert_eq!(OcrInput::from_url("https://example.com").file_name(), None);
+    }
+
+    #[test]
+    fn profile_paths_become_file_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("my profile 100%");
+        let url = file_url(&path).unwrap();
+        assert!(url.starts_with("file://"), "{url}");
+        assert!(url.ends_with("/my%20profile%20100%25"), "{url}");
+        assert_eq!(
+            reqwest::Url::parse(&url).unwrap().to_file_path().unwrap(),
+            path
+        );
+        assert!(file_url(Path::new("relative/profile")).is_err());
+    }
+
+    #[test]
+    fn reader_input_is_buffered() {
+        let input = OcrInput::from_reader(&b"%PDF-1.4"[..])
+            .unwrap()
+            .with_mime_type("application/pdf");
+        let (kind, data) = input.prepare().unwrap();
+        assert_eq!(kind, InputKind::Pdf);
+        assert_eq!(&*data, b"%PDF-1.4");
+    }
+}
@@ -1,30 +1,65 @@
-use anyhow::{Context, Result, bail};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::time::Duration;
+use std::time::SystemTime;
 use tracing::{info, warn};
 use zip::write::SimpleFileOptions;
 
-const API_URL: &str = "https://api.mistral.ai/v1/ocr";
-
-const MODEL: &str = "mistral-ocr-latest";
-
-/// Mistral's documented upload limit for OCR documents.
+mod annotation;
+#[cfg(feature = "async")]
+mod async_client;
+mod batch;
+mod cache;
+mod client;
+mod document;
+mod error;
+mod files;
+mod flow;
+mod front_matter;
+mod html;
+mod input;
+mod pages;
+mod rate_limit;
+mod response;
+mod runner;
+mod running_headers;
+mod split;
+mod tables;
+mod text;
+
+pub use annotation::{AnnotationSchema, annotations_path, write_annotations};
+#[cfg(feature = "async")]
+pub use async_client::AsyncOcrClient;
+pub use batch::{BatchEntry, BatchJobStatus, BatchState, BatchStatus};
+pub use cache::{CacheEntry, ResultCache};
+pub use client::{DEFAULT_BASE_URL, DEFAULT_MODEL, OcrClient, OcrClientBuilder, RetryPolicy};
+pub use document::{DocumentImage, DocumentPage, OcrDocument, SourceInfo};
+use error::IoContext;
+pub use error::{OcrError, Result};
+pub use files::UploadMode;
+use front_matter::front_matter;
+pub use html::write_html;
+pub use input::OcrInput;
+pub use pages::PageSelection;
+pub use response::{BoundingBox, OcrImage, OcrPage, OcrResponse, PageDimensions, UsageInfo};
+pub use runner::{BatchJob, BatchProgress, BatchRunner};
+pub use tables::{Table, TableFormat, tables_path, write_tables};
+pub use text::write_text;
+
+/// Mistral's documented upload limit for OCR documents; larger PDFs are split.
 const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
 
-const MAX_ATTEMPTS: u32 = 3;
-
 pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif", "webp"];
 pub const CONVERTIBLE_EXTENSIONS: &[&str] = &[
     "doc", "docx", "odt", "rtf", "txt", "html", "htm", "pptx", "ppt", "odp", "xlsx", "xls", "ods",
     "csv", "epub",
 ];
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ImageMode {
     None,
     Separate,
@@ -32,11 +67,28 @@ pub enum ImageMode {
     Zip,
 }
 
-/// RAII guard that removes a temp file on drop.
-struct TempCleanup(PathBuf);
-impl Drop for TempCleanup {
-    fn drop(&mut self) {
-        let _ = fs::remove_file(&self.0);
+/// File format written by [`write_output`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Markdown,
+    /// The [`OcrResponse`] schema, see [`write_json`].
+    Json,
+    /// A standalone page, see [`write_html`].
+    Html,
+    /// Markdown syntax stripped, see [`write_text`].
+    Text,
+}
+
+impl OutputFormat {
+    /// Conventional file extension for outputs in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+            OutputFormat::Html => "html",
+            OutputFormat::Text => "txt",
+        }
     }
 }
 
@@ -46,6 +98,17 @@ struct OcrRequest {
     document: Document,
     #[serde(skip_serializing_if = "Option::is_none")]
     include_image_base64: Option<bool>,
+    /// Zero-based indices of the pages to process; all pages when absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pages: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_annotation_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bbox_annotation_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extract_header: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extract_footer: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -60,25 +123,6 @@ enum Document {
     ImageUrl { image_url: String },
 }
 
-#[derive(Deserialize)]
-struct OcrResponse {
-    pages: Vec<OcrPage>,
-}
-
-#[derive(Deserialize)]
-struct OcrPage {
-    index: u32,
-    markdown: String,
-    #[serde(default)]
-    images: Vec<OcrImage>,
-}
-
-#[derive(Deserialize)]
-struct OcrImage {
-    id: Option<String>,
-    image_base64: Option<String>,
-}
-
 fn mime_for_ext(ext: &str) -> &'static str {
     match ext {
         "jpg" | "jpeg" => "image/jpeg",
@@ -91,345 +135,420 @@ fn mime_for_ext(ext: &str) -> &'static str {
     }
 }
 
-fn find_libreoffice() -> Result<PathBuf> {
-    for name in &["libreoffice", "soffice"] {
-        if let Ok(output) = Command::new("which").arg(name).output()
-            && output.status.success()
-        {
-            return Ok(PathBuf::from(name));
-        }
-        if let Ok(output) = Command::new("where").arg(name).output()
-            && output.status.success()
-        {
-            return Ok(PathBuf::from(name));
-        }
-    }
-
-    let candidates: &[&str] = if cfg!(target_os = "macos") {
-        &[
-            "/Applications/LibreOffice.app/Contents/MacOS/soffice",
-            "/opt/homebrew/bin/soffice",
-        ]
-    } else if cfg!(target_os = "windows") {
-        &[
-            r"C:\Program Files\LibreOffice\program\soffice.exe",
-            r"C:\Program Files (x86)\LibreOffice\program\soffice.exe",
-        ]
-    } else {
-        &["/usr/bin/libreoffice", "/usr/bin/soffice"]
-    };
-
-    for path in candidates {
-        if Path::new(path).exists() {
-            return Ok(PathBuf::from(path));
-        }
-    }
-
-    bail!(
-        "LibreOffice not found. Install it from https://www.libreoffice.org/\n\
-         LibreOffice is only needed for office document conversion (docx, odt, pptx, etc.).\n\
-         PDF and image files work without it."
-    )
-}
-
-fn convert_to_pdf(input_path: &Path) -> Result<PathBuf> {
-    let lo_bin = find_libreoffice()?;
-    let temp_dir = std::env::temp_dir().join("mistral_ocr");
-    fs::create_dir_all(&temp_dir)?;
-
-    let output = Command::new(&lo_bin)
-        .args(["--headless", "--convert-to", "pdf", "--outdir"])
-        .arg(&temp_dir)
-        .arg(input_path)
-        .output()
-        .with_context(|| format!("Failed to run LibreOffice at {}", lo_bin.display()))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("libreoffice conversion failed: {stderr}");
-    }
-
-    let stem = input_path.file_stem().context("Input file has no stem")?;
-    let pdf_path = temp_dir.join(format!("{}.pdf", stem.to_string_lossy()));
-
-    if !pdf_path.exists() {
-        bail!(
-            "libreoffice did not produce expected PDF at {}",
-            pdf_path.display()
-        );
-    }
-
-    Ok(pdf_path)
-}
-
-fn encode_file(path: &Path) -> Result<String> {
-    let data = fs::read(path).with_context(|| format!("File not found: {}", path.display()))?;
-    Ok(BASE64.encode(&data))
-}
-
-/// Options controlling OCR output rendering.
-#[derive(Clone, Copy, Debug)]
+/// Options controlling what is requested from the API and how the output is rendered.
+///
+/// Serializable so they can be stored with work that is rendered later, such as a
+/// pending batch job.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OcrOptions {
+    pub format: OutputFormat,
     pub image_mode: ImageMode,
     /// Insert `# Page N` headers between pages of multi-page documents.
     pub page_headers: bool,
+    /// Template for the header written before each page of multi-page markdown instead
+    /// of `# Page N`, e.g. `<!-- page {n} -->` or `## Page {n} of {total}`. `{n}` is the
+    /// page number, `{total}` the number of pages in the output and `{source}` the
+    /// source file name. Ignored unless [`page_headers`](Self::page_headers) is set.
+    /// With [`continuous`](Self::continuous) it may be placed mid-paragraph, so it should be
+    /// inline markup such as a comment or an anchor.
+    pub page_separator: Option<String>,
+    /// Write markdown as one continuous text: paragraphs and hyphenated words split at
+    /// page breaks are rejoined, and page headers become `<a id="page-N"></a>` anchors
+    /// placed where each page starts, mid-paragraph if need be.
+    pub continuous: bool,
+    /// Only OCR these pages of a PDF; page headers keep the original page numbers.
+    pub pages: Option<PageSelection>,
+    /// Write markdown as one file per page in a directory named after the output,
+    /// e.g. `doc/page-001.md` for `doc.md`, instead of one concatenated file.
+    pub per_page: bool,
+    /// Start markdown with YAML front matter: source file name and SHA-256, page count,
+    /// model, processing time, tool version and API usage.
+    pub front_matter: bool,
+    /// Have the API fill in this schema for the document as a whole; the result is in
+    /// [`OcrDocument::document_annotation`].
+    pub document_annotation_format: Option<AnnotationSchema>,
+    /// Have the API fill in this schema for each extracted image; the results are in
+    /// [`DocumentImage::annotation`].
+    pub bbox_annotation_format: Option<AnnotationSchema>,
+    /// Also write the tables found in the page markdown next to the output, see
+    /// [`write_tables`].
+    pub tables: Option<TableFormat>,
+    /// Remove running headers, footers and page numbers from the page markdown before
+    /// rendering; see [`OcrDocument::strip_running_headers`].
+    pub strip_headers: bool,
+    /// Ask the API to move running headers and footers out of the page markdown into
+    /// [`DocumentPage::header`] and [`DocumentPage::footer`]. Only some models support
+    /// this; others may reject the request.
+    pub extract_headers: bool,
+    /// In HTML output with math, load MathJax from its CDN to render it. Without it, or
+    /// offline, math shows as TeX.
+    pub mathjax: bool,
 }
 
 impl Default for OcrOptions {
     fn default() -> Self {
         Self {
+            format: OutputFormat::Markdown,
             image_mode: ImageMode::None,
             page_headers: true,
+            page_separator: None,
+            continuous: false,
+            pages: None,
+            per_page: false,
+            front_matter: false,
+            document_annotation_format: None,
+            bbox_annotation_format: None,
+            tables: None,
+            strip_headers: false,
+            extract_headers: false,
+            mathjax: true,
+        }
+    }
+}
+
+impl OcrOptions {
+    /// Header line for page `number` (1-based) of `total` in markdown output.
+    fn page_header(&self, number: u32, total: usize, source: &str) -> String {
+        match &self.page_separator {
+            Some(template) => template
+                .replace("{n}", &number.to_string())
+                .replace("{total}", &total.to_string())
+                .replace("{source}", source),
+            None if self.continuous => format!("<a id=\"page-{number}\"></a>"),
+            None => format!("# Page {number}"),
+        }
+    }
+
+    /// File actually written for `output_path`: the `.zip` bundle in [`ImageMode::Zip`],
+    /// except for plain text, which has no images to bundle, or the directory of
+    /// [`per_page`](Self::per_page) markdown files.
+    pub fn written_path(&self, output_path: &Path) -> PathBuf {
+        if self.image_mode == ImageMode::Zip && self.format != OutputFormat::Text {
+            output_path.with_extension("zip")
+        } else if self.per_page && self.format == OutputFormat::Markdown {
+            output_path.with_extension("")
+        } else {
+            output_path.to_path_buf()
         }
     }
 }
 
+/// OCR a file with a default [`OcrClient`] and write the result to `output_path`.
 pub fn run_ocr(
     input_path: &Path,
-    options: OcrOptions,
+    options: &OcrOptions,
     output_path: &Path,
     api_key: &str,
 ) -> Result<()> {
-    let image_mode = options.image_mode;
-    let ext = input_path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
-    let temp_pdf: Option<PathBuf>;
-    let effective_path;
-    if CONVERTIBLE_EXTENSIONS.contains(&ext.as_str()) {
-        info!("Converting .{ext} to PDF via LibreOffice...");
-        temp_pdf = Some(convert_to_pdf(input_path)?);
-        effective_path = temp_pdf.as_deref().unwrap().to_path_buf();
-    } else {
-        temp_pdf = None;
-        effective_path = input_path.to_path_buf();
+    OcrClient::new(api_key)?.run_ocr(input_path, options, output_path)
+}
+
+/// Render an OCR response at `output_path` in `options.format`.
+///
+/// Except for JSON output, which already contains them, annotations are written to a
+/// sidecar file with [`write_annotations`]. Tables are written with [`write_tables`] if
+/// [`OcrOptions::tables`] is set. With [`OcrOptions::strip_headers`], running headers and
+/// footers are removed from the pages first, except for JSON output, which keeps the
+/// response as it is.
+pub fn write_output(
+    output_path: &Path,
+    response: OcrResponse,
+    source: SourceInfo,
+    options: &OcrOptions,
+) -> Result<()> {
+    if options.format == OutputFormat::Json {
+        write_json(output_path, &response, options)?;
+        if let Some(format) = options.tables {
+            let document = OcrDocument::from_raw(response, source.name)?;
+            write_tables(output_path, &document, format)?;
+        }
+        return Ok(());
+    }
+    let mut document = OcrDocument::from_raw(response, source.name)?;
+    document.source_sha256 = source.sha256;
+    if options.strip_headers {
+        for line in document.strip_running_headers() {
+            info!("Removed running header/footer: {line}");
+        }
     }
+    match options.format {
+        OutputFormat::Markdown => write_markdown(output_path, &document, options)?,
+        OutputFormat::Html => write_html(output_path, &document, options)?,
+        OutputFormat::Text => write_text(output_path, &document, options)?,
+        OutputFormat::Json => unreachable!(),
+    }
+    write_annotations(output_path, &document)?;
+    if let Some(format) = options.tables {
+        write_tables(output_path, &document, format)?;
+    }
+    Ok(())
+}
 
-    let _cleanup = temp_pdf.as_ref().map(|p| TempCleanup(p.clone()));
+/// Render an OCR result as markdown at `output_path`, handling images per `options.image_mode`.
+///
+/// In [`ImageMode::Zip`] mode the markdown and images are bundled into a `.zip` next to
+/// `output_path` instead. With [`OcrOptions::per_page`], see [`write_markdown_pages`];
+/// [`OcrOptions::continuous`] does not apply to per-page files.
+pub fn write_markdown(
+    output_path: &Path,
+    document: &OcrDocument,
+    options: &OcrOptions,
+) -> Result<()> {
+    if options.per_page {
+        return write_markdown_pages(output_path, document, options);
+    }
+    create_parent_dir(output_path)?;
+    let mut images = ImageWriter::new(output_path, options.image_mode);
 
-    let effective_ext = effective_path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
+    let mut output = String::new();
+    if options.front_matter {
+        output.push_str(&front_matter(document, None, SystemTime::now()));
+    }
+    let total = document.pages.len();
+    let source = document
+        .source_name
+        .clone()
+        .unwrap_or_else(|| output_stem(output_path));
+
+    if options.continuous {
+        let mut pages = Vec::with_capacity(total);
+        for page in &document.pages {
+            let header = (total > 1 && options.page_headers)
+                .then(|| options.page_header(page.index + 1, total, &source));
+            pages.push((header, images.page_markdown(page)?));
+        }
+        output.push_str(&flow::join_pages(pages));
+        return images.finish(output_path, "md", output.as_bytes());
+    }
 
-    let file_size = fs::metadata(&effective_path)
-        .with_context(|| format!("File not found: {}", effective_path.display()))?
-        .len();
-    if file_size > MAX_FILE_SIZE {
-        bail!(
-            "File is {:.1} MB, which exceeds the Mistral OCR upload limit of {} MB",
-            file_size as f64 / (1024.0 * 1024.0),
-            MAX_FILE_SIZE / (1024 * 1024)
-        );
+    for page in &document.pages {
+        let md = images.page_markdown(page)?;
+        if total > 1 && options.page_headers {
+            output.push_str(&options.page_header(page.index + 1, total, &source));
+            output.push_str("\n\n");
+        }
+        output.push_str(&md);
+        output.push_str("\n\n");
     }
 
-    info!("Encoding file...");
-    let b64 = encode_file(&effective_path)?;
+    images.finish(output_path, "md", output.as_bytes())
+}
 
-    let document = if effective_ext == "pdf" {
-        let file_name = input_path
-            .file_name()
-            .map(|n| n.to_string_lossy().into_owned())
-            .unwrap_or_default();
-        Document::DocumentUrl {
-            document_url: format!("data:application/pdf;base64,{b64}"),
-            document_name: file_name,
-        }
-    } else if IMAGE_EXTENSIONS.contains(&effective_ext.as_str()) {
-        let mime = mime_for_ext(&effective_ext);
-        Document::ImageUrl {
-            image_url: format!("data:{mime};base64,{b64}"),
+/// Render each page as its own markdown file, `page-001.md` and so on (numbered by the
+/// original page number), in a directory named after `output_path` without its
+/// extension.
+///
+/// Each page gets its own `page-001_images` directory in [`ImageMode::Separate`]. In
+/// [`ImageMode::Zip`] all pages go into one `.zip` next to `output_path` instead, with
+/// their images in its `images` folder.
+pub fn write_markdown_pages(
+    output_path: &Path,
+    document: &OcrDocument,
+    options: &OcrOptions,
+) -> Result<()> {
+    let dir = output_path.with_extension("");
+    let last_page = document
+        .pages
+        .iter()
+        .map(|p| p.index + 1)
+        .max()
+        .unwrap_or(1);
+    let width = last_page.to_string().len().max(3);
+    let page_name = |index: u32| format!("page-{:0width$}.md", index + 1);
+    let now = SystemTime::now();
+    let page_file = |page: &DocumentPage, md: String| {
+        if options.front_matter {
+            format!("{}{md}\n", front_matter(document, Some(page.index), now))
+        } else {
+            format!("{md}\n")
         }
-    } else {
-        bail!(
-            "Unsupported file type: .{ext} (expected pdf, image, or document: docx, odt, pptx, xlsx, etc.)"
-        );
     };
 
-    let include_image_base64 = match image_mode {
-        ImageMode::None => None,
-        ImageMode::Separate | ImageMode::Inline | ImageMode::Zip => Some(true),
-    };
+    if options.image_mode == ImageMode::Zip {
+        create_parent_dir(output_path)?;
+        let mut images = ImageWriter::new(output_path, ImageMode::Zip);
+        let mut files = Vec::with_capacity(document.pages.len());
+        for page in &document.pages {
+            let md = images.page_markdown(page)?;
+            files.push((page_name(page.index), page_file(page, md).into_bytes()));
+        }
+        return images.write_zip(&output_path.with_extension("zip"), &files);
+    }
 
-    let request = OcrRequest {
-        model: MODEL.to_string(),
-        document,
-        include_image_base64,
-    };
+    fs::create_dir_all(&dir).io_context(|| format!("Failed to create {}", dir.display()))?;
+    for page in &document.pages {
+        let path = dir.join(page_name(page.index));
+        let mut images = ImageWriter::new(&path, options.image_mode);
+        let md = images.page_markdown(page)?;
+        images.finish(&path, "md", page_file(page, md).as_bytes())?;
+    }
+    Ok(())
+}
 
-    info!("Sending OCR request to Mistral API...");
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(300))
-        .build()
-        .context("Failed to build HTTP client")?;
-
-    let mut attempt = 0;
-    let response = loop {
-        attempt += 1;
-        let result = client
-            .post(API_URL)
-            .bearer_auth(api_key)
-            .json(&request)
-            .send();
-
-        let retry_delay = Duration::from_secs(1 << attempt); // 2s, 4s
-        match result {
-            Ok(resp) => {
-                let status = resp.status();
-                let transient =
-                    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
-                if transient && attempt < MAX_ATTEMPTS {
-                    warn!(
-                        "OCR request failed (HTTP {status}), retrying in {}s (attempt {attempt}/{MAX_ATTEMPTS})...",
-                        retry_delay.as_secs()
-                    );
-                    std::thread::sleep(retry_delay);
-                    continue;
-                }
-                break resp;
+/// Write `response` as pretty-printed JSON in the [`OcrResponse`] schema.
+///
+/// Images follow `options.image_mode`: dropped with [`ImageMode::None`] and kept as
+/// `image_base64` with [`ImageMode::Inline`]. With [`ImageMode::Separate`] and
+/// [`ImageMode::Zip`] they are written out as files like for markdown, `image_base64` is
+/// removed and image links in each page's `markdown` point at the files; image `id`s
+/// are left unchanged.
+pub fn write_json(output_path: &Path, response: &OcrResponse, options: &OcrOptions) -> Result<()> {
+    let image_mode = options.image_mode;
+    create_parent_dir(output_path)?;
+    let mut images = ImageWriter::new(output_path, image_mode);
+
+    let mut response = response.clone();
+    for page in &mut response.pages {
+        for img in &mut page.images {
+            if image_mode == ImageMode::Inline {
+                continue;
             }
-            Err(err) if (err.is_timeout() || err.is_connect()) && attempt < MAX_ATTEMPTS => {
-                warn!(
-                    "OCR request failed ({err}), retrying in {}s (attempt {attempt}/{MAX_ATTEMPTS})...",
-                    retry_delay.as_secs()
-                );
-                std::thread::sleep(retry_delay);
+            let (Some(b64), Some(id)) = (img.image_base64.take(), &img.id) else {
+                continue;
+            };
+            if image_mode == ImageMode::None {
                 continue;
             }
-            Err(err) => return Err(err).context("OCR request failed"),
+            let link = images.write(id, "", decode_image_base64(&b64, id)?)?;
+            page.markdown = page
+                .markdown
+                .replace(&format!("]({id})"), &format!("]({link})"));
         }
-    };
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().unwrap_or_default();
-        bail!("OCR request failed (HTTP {status}): {body}");
     }
 
-    info!("Processing response...");
-    let ocr: OcrResponse = response.json().context("Failed to parse OCR response")?;
-    write_markdown(output_path, &ocr, options)?;
-
-    if image_mode == ImageMode::Zip {
-        info!(
-            "Done! Output written to {}",
-            output_path.with_extension("zip").display()
-        );
-    } else {
-        info!("Done! Output written to {}", output_path.display());
-    }
-    Ok(())
+    let json = serde_json::to_string_pretty(&response)
+        .map_err(|e| OcrError::decode("Failed to serialize OCR response", e))?;
+    images.finish(output_path, "json", json.as_bytes())
 }
 
-fn write_markdown(output_path: &Path, response: &OcrResponse, options: OcrOptions) -> Result<()> {
-    let image_mode = options.image_mode;
+/// Folder inside a zip bundle that images are stored in.
+const ZIP_IMAGES_DIR: &str = "images";
+
+pub(crate) fn create_parent_dir(output_path: &Path) -> Result<()> {
     if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
+        fs::create_dir_all(parent)
+            .io_context(|| format!("Failed to create {}", parent.display()))?;
     }
+    Ok(())
+}
 
-    let stem = output_path
+pub(crate) fn output_stem(output_path: &Path) -> String {
+    output_path
         .file_stem()
         .map(|s| s.to_string_lossy().into_owned())
-        .unwrap_or_else(|| "output".to_string());
-
-    let images_dir = if image_mode == ImageMode::Separate {
-        Some(
-            output_path
-                .parent()
-                .unwrap_or(Path::new("."))
-                .join(format!("{stem}_images")),
-        )
-    } else {
-        None
-    };
+        .unwrap_or_else(|| "output".to_string())
+}
 
-    let mut zip_images: Vec<(String, Vec<u8>)> = Vec::new();
-    let images_subdir = "images";
+/// Stores the images of one rendered output according to its [`ImageMode`]: in a
+/// `<stem>_images` directory next to it, as data URIs, or in its zip bundle.
+pub(crate) struct ImageWriter {
+    mode: ImageMode,
+    dir: PathBuf,
+    zip_images: Vec<(String, Vec<u8>)>,
+}
 
-    let mut output = String::new();
-    let multi_page = response.pages.len() > 1;
+impl ImageWriter {
+    pub(crate) fn new(output_path: &Path, mode: ImageMode) -> Self {
+        let dir = output_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(format!("{}_images", output_stem(output_path)));
+        Self {
+            mode,
+            dir,
+            zip_images: Vec::new(),
+        }
+    }
 
-    for page in &response.pages {
+    /// Store one image and return the link to use for it in place of its id.
+    /// Not called in [`ImageMode::None`].
+    fn write(&mut self, id: &str, mime_type: &str, data: Vec<u8>) -> Result<String> {
+        match self.mode {
+            ImageMode::Separate => {
+                let dir = &self.dir;
+                fs::create_dir_all(dir)
+                    .io_context(|| format!("Failed to create {}", dir.display()))?;
+                fs::write(dir.join(id), data)
+                    .io_context(|| format!("Failed to write image {id}"))?;
+                let dir_name = dir.file_name().unwrap().to_string_lossy();
+                Ok(format!("{dir_name}/{id}"))
+            }
+            ImageMode::Inline => Ok(format!("data:{mime_type};base64,{}", BASE64.encode(data))),
+            ImageMode::Zip => {
+                self.zip_images.push((id.to_string(), data));
+                Ok(format!("{ZIP_IMAGES_DIR}/{id}"))
+            }
+            ImageMode::None => unreachable!(),
+        }
+    }
+
+    /// Page markdown, trimmed, with image links pointing at the stored images.
+    pub(crate) fn page_markdown(&mut self, page: &DocumentPage) -> Result<String> {
         let mut md = page.markdown.trim_end().to_string();
+        for (id, link) in self.page_links(page)? {
+            md = md.replace(&format!("]({id})"), &format!("]({link})"));
+        }
+        Ok(md)
+    }
 
-        if image_mode != ImageMode::None {
-            for img in &page.images {
-                let (Some(id), Some(b64_data)) = (&img.id, &img.image_base64) else {
-                    match &img.id {
-                        Some(id) => warn!(
-                            "Image {id} on page {} has no data; its link will be dangling",
-                            page.index + 1
-                        ),
-                        None => warn!("Image without id on page {} skipped", page.index + 1),
-                    }
-                    continue;
-                };
-                let old_ref = format!("]({id})");
-
-                match image_mode {
-                    ImageMode::Separate => {
-                        let dir = images_dir.as_ref().unwrap();
-                        let decoded = decode_image_base64(b64_data, id)?;
-                        fs::create_dir_all(dir)?;
-                        fs::write(dir.join(id), &decoded)
-                            .with_context(|| format!("Failed to write image {id}"))?;
-                        let dir_name = dir.file_name().unwrap().to_string_lossy();
-                        md = md.replace(&old_ref, &format!("]({dir_name}/{id})"));
-                    }
-                    ImageMode::Inline => {
-                        let data_uri = if b64_data.starts_with("data:") {
-                            b64_data.clone()
-                        } else {
-                            let img_ext = Path::new(id)
-                                .extension()
-                                .map(|e| e.to_string_lossy().to_lowercase())
-                                .unwrap_or_else(|| "jpeg".to_string());
-                            let mime = mime_for_ext(&img_ext);
-                            format!("data:{mime};base64,{b64_data}")
-                        };
-                        md = md.replace(&old_ref, &format!("]({data_uri})"));
-                    }
-                    ImageMode::Zip => {
-                        let decoded = decode_image_base64(b64_data, id)?;
-                        zip_images.push((id.clone(), decoded));
-                        md = md.replace(&old_ref, &format!("]({images_subdir}/{id})"));
-                    }
-                    ImageMode::None => unreachable!(),
-                }
-            }
+    /// Store the images of `page` and return the link for each image id.
+    pub(crate) fn page_links(&mut self, page: &DocumentPage) -> Result<HashMap<String, String>> {
+        let mut links = HashMap::new();
+        if self.mode == ImageMode::None {
+            return Ok(links);
+        }
+        for img in &page.images {
+            let id = &img.id;
+            let Some(data) = &img.data else {
+                warn!(
+                    "Image {id} on page {} has no data; its link will be dangling",
+                    page.index + 1
+                );
+                continue;
+            };
+            let link = self.write(id, &img.mime_type, data.clone())?;
+            links.insert(id.clone(), link);
         }
+        Ok(links)
+    }
 
-        if multi_page && options.page_headers {
-            output.push_str(&format!("# Page {}\n\n", page.index + 1));
+    /// Write the rendered output to `output_path`, or in [`ImageMode::Zip`] bundle it
+    /// as `<stem>.<extension>` with the images into a `.zip` next to it.
+    pub(crate) fn finish(self, output_path: &Path, extension: &str, contents: &[u8]) -> Result<()> {
+        if self.mode != ImageMode::Zip {
+            return fs::write(output_path, contents)
+                .io_context(|| format!("Failed to write {}", output_path.display()));
         }
-        output.push_str(&md);
-        output.push_str("\n\n");
+        let name = format!("{}.{extension}", output_stem(output_path));
+        self.write_zip(
+            &output_path.with_extension("zip"),
+            &[(name, contents.to_vec())],
+        )
     }
 
-    if image_mode == ImageMode::Zip {
-        let zip_path = output_path.with_extension("zip");
-        let file = fs::File::create(&zip_path).context("Failed to create zip file")?;
+    /// Write `files` and the collected images into a zip at `zip_path`.
+    pub(crate) fn write_zip(self, zip_path: &Path, files: &[(String, Vec<u8>)]) -> Result<()> {
+        let file = fs::File::create(zip_path).io_context(|| "Failed to create zip file")?;
         let mut zip = zip::ZipWriter::new(file);
         let options =
             SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
 
-        let md_name = format!("{stem}.md");
-        zip.start_file(&md_name, options)?;
-        zip.write_all(output.as_bytes())?;
+        for (name, contents) in files {
+            zip.start_file(name, options)?;
+            zip.write_all(contents)
+                .io_context(|| "Failed to write zip archive")?;
+        }
 
-        for (name, data) in &zip_images {
-            zip.start_file(format!("{images_subdir}/{name}"), options)?;
-            zip.write_all(data)?;
+        for (name, data) in &self.zip_images {
+            zip.start_file(format!("{ZIP_IMAGES_DIR}/{name}"), options)?;
+            zip.write_all(data)
+                .io_context(|| "Failed to write zip archive")?;
         }
 
         zip.finish()?;
-    } else {
-        fs::write(output_path, &output).context("Failed to write markdown output")?;
+        Ok(())
     }
-
-    Ok(())
 }
 
 fn decode_image_base64(b64_data: &str, id: &str) -> Result<Vec<u8>> {
@@ -440,13 +559,14 @@ fn decode_image_base64(b64_data: &str, id: &str) -> Result<Vec<u8>> {
     };
     BASE64
         .decode(raw)
-        .with_context(|| format!("Failed to decode base64 for image {id}"))
+        .map_err(|e| OcrError::decode(format!("Failed to decode base64 for image {id}"), e))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Read;
+    use std::time::Duration;
 
     fn sample_response() -> OcrResponse {
         OcrResponse {
@@ -460,17 +580,617 @@ mod tests {
                             "data:image/jpeg;base64,{}",
                             BASE64.encode(b"fake-jpeg-data")
                         )),
+                        ..Default::default()
                     }],
+                    ..Default::default()
                 },
                 OcrPage {
                     index: 1,
                     markdown: "Second page".to_string(),
-                    images: vec![],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         }
     }
 
+    fn sample_document() -> OcrDocument {
+        OcrDocument::from_response(
+            sample_response(),
+            Some("doc.pdf".to_string()),
+            DEFAULT_MODEL,
+        )
+        .unwrap()
+    }
+
+    fn mock_response_body() -> String {
+        serde_json::json!({
+            "pages": [{"index": 0, "markdown": "Hello from mock", "images": []}]
+        })
+        .to_string()
+    }
+
+    fn mock_client(server: &mockito::Server) -> OcrClient {
+        OcrClient::builder("test-key")
+            .base_url(server.url())
+            .retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn builder_defaults_and_trailing_slash() {
+        let client = OcrClient::new("key").unwrap();
+        assert_eq!(client.base_url(), DEFAULT_BASE_URL);
+        assert_eq!(client.model(), DEFAULT_MODEL);
+
+        let client = OcrClient::builder("key")
+            .base_url("http://localhost:8080/v1/")
+            .model("mistral-ocr-2505")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://localhost:8080/v1");
+        assert_eq!(client.model(), "mistral-ocr-2505");
+    }
+
+    #[test]
+    fn client_uses_configured_base_url_and_model() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_header("authorization", "Bearer test-key")
+            .match_header("user-agent", "custom-agent")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"model": "pinned-model"}),
+            ))
+            .with_body(mock_response_body())
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF-1.4 fake").unwrap();
+        let out = dir.path().join("doc.md");
+
+        let client = OcrClient::builder("test-key")
+            .base_url(server.url())
+            .model("pinned-model")
+            .user_agent("custom-agent")
+            .build()
+            .unwrap();
+        client
+            .run_ocr(&input, &OcrOptions::default(), &out)
+            .unwrap();
+
+        mock.assert();
+        let md = fs::read_to_string(&out).unwrap();
+        assert!(md.contains("Hello from mock"));
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        let mut server = mockito::Server::new();
+        let failing = server
+            .mock("POST", "/ocr")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let ok = server
+            .mock("POST", "/ocr")
+            .with_body(mock_response_body())
+            .expect(1)
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF-1.4 fake").unwrap();
+        let out = dir.path().join("doc.md");
+
+        mock_client(&server)
+            .run_ocr(&input, &OcrOptions::default(), &out)
+            .unwrap();
+
+        failing.assert();
+        ok.assert();
+    }
+
+    #[test]
+    fn retry_after_is_honoured_up_to_the_max_delay() {
+        let mut server = mockito::Server::new();
+        let limited = server
+            .mock("POST", "/ocr")
+            .with_status(429)
+            .with_header("retry-after", "30")
+            .expect(1)
+            .create();
+        let ok = server
+            .mock("POST", "/ocr")
+            .with_body(mock_response_body())
+            .expect(1)
+            .create();
+
+        let client = OcrClient::builder("test-key")
+            .base_url(server.url())
+            .retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_delay: Duration::ZERO,
+                max_delay: Duration::from_millis(200),
+            })
+            .build()
+            .unwrap();
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+        let started = std::time::Instant::now();
+        client
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(200), "{waited:?}");
+        assert!(waited < Duration::from_secs(10), "{waited:?}");
+        limited.assert();
+        ok.assert();
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .with_status(401)
+            .with_body("unauthorized")
+            .expect(1)
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF-1.4 fake").unwrap();
+
+        let err = mock_client(&server)
+            .run_ocr(&input, &OcrOptions::default(), &dir.path().join("doc.md"))
+            .unwrap_err();
+        assert!(matches!(err, OcrError::Api { status: 401, ref body } if body == "unauthorized"));
+        assert!(!err.is_retryable());
+        mock.assert();
+    }
+
+    #[test]
+    fn exhausted_rate_limit_is_reported_as_rate_limited() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .with_status(429)
+            .with_header("retry-after", "30")
+            .expect(2)
+            .create();
+
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+        let err = mock_client(&server)
+            .process_input(&input, &OcrOptions::default())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OcrError::RateLimited {
+                retry_after: Some(d),
+                ..
+            } if d == Duration::from_secs(30)
+        ));
+        assert!(err.is_retryable());
+        mock.assert();
+    }
+
+    #[test]
+    fn process_returns_document_without_writing() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(mock_response_body())
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF-1.4 fake").unwrap();
+
+        let document = mock_client(&server)
+            .process(&input, &OcrOptions::default())
+            .unwrap();
+        assert_eq!(document.source_name.as_deref(), Some("doc.pdf"));
+        assert_eq!(document.model, DEFAULT_MODEL);
+        assert_eq!(document.pages.len(), 1);
+        assert_eq!(document.pages[0].markdown, "Hello from mock");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn document_exposes_layout_usage_and_model() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(
+                serde_json::json!({
+                    "model": "mistral-ocr-2505",
+                    "usage_info": {"pages_processed": 1, "doc_size_bytes": 13},
+                    "pages": [{
+                        "index": 0,
+                        "markdown": "![img-0.jpeg](img-0.jpeg)",
+                        "dimensions": {"dpi": 200, "height": 2200, "width": 1700},
+                        "images": [{
+                            "id": "img-0.jpeg",
+                            "top_left_x": 150, "top_left_y": 300,
+                            "bottom_right_x": 900, "bottom_right_y": 700,
+                        }],
+                    }],
+                })
+                .to_string(),
+            )
+            .create();
+        let input = OcrInput::from_bytes(&b"%PDF-1.4 fake"[..]).with_file_name("doc.pdf");
+        let document = mock_client(&server)
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+
+        assert_eq!(document.model, "mistral-ocr-2505");
+        assert_eq!(document.usage_info.unwrap().doc_size_bytes, Some(13));
+        let page = &document.pages[0];
+        assert_eq!(
+            page.dimensions,
+            Some(PageDimensions {
+                dpi: 200,
+                height: 2200,
+                width: 1700
+            })
+        );
+        assert_eq!(
+            page.images[0].bbox,
+            Some(BoundingBox {
+                top_left_x: 150,
+                top_left_y: 300,
+                bottom_right_x: 900,
+                bottom_right_y: 700
+            })
+        );
+    }
+
+    #[test]
+    fn in_memory_input_is_sent_with_declared_type() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "document": {
+                    "type": "image_url",
+                    "image_url": format!("data:image/png;base64,{}", BASE64.encode(b"png-bytes")),
+                }
+            })))
+            .with_body(mock_response_body())
+            .create();
+
+        let input = OcrInput::from_bytes(&b"png-bytes"[..]).with_mime_type("image/png");
+        let document = mock_client(&server)
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        mock.assert();
+        assert_eq!(document.source_name, None);
+    }
+
+    #[test]
+    fn remote_url_is_passed_through_even_in_files_mode() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "document": {
+                    "type": "document_url",
+                    "document_url": "https://example.com/pdf/2401.00001",
+                    "document_name": "2401.00001",
+                }
+            })))
+            .with_body(mock_response_body())
+            .create();
+        let upload = server.mock("POST", "/files").expect(0).create();
+
+        let client = OcrClient::builder("test-key")
+            .base_url(server.url())
+            .upload_mode(UploadMode::Files { delete_after: true })
+            .build()
+            .unwrap();
+        let input = OcrInput::from_url("https://example.com/pdf/2401.00001");
+        let document = client
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        mock.assert();
+        upload.assert();
+        assert_eq!(document.source_name.as_deref(), Some("2401.00001"));
+    }
+
+    #[test]
+    fn downloaded_url_is_sent_inline_with_served_type() {
+        let mut server = mockito::Server::new();
+        let download = server
+            .mock("GET", "/files/scan")
+            .with_header("content-type", "image/png")
+            .with_body("png-bytes")
+            .create();
+        let ocr = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "document": {
+                    "type": "image_url",
+                    "image_url": format!("data:image/png;base64,{}", BASE64.encode(b"png-bytes")),
+                }
+            })))
+            .with_body(mock_response_body())
+            .create();
+
+        let client = mock_client(&server);
+        let input = client
+            .download(&format!("{}/files/scan", server.url()))
+            .unwrap();
+        assert_eq!(input.file_name(), Some("scan"));
+        assert_eq!(input.mime_type(), Some("image/png"));
+        client
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        download.assert();
+        ocr.assert();
+    }
+
+    #[test]
+    fn cached_results_are_reused_across_image_modes() {
+        let mut server = mockito::Server::new();
+        let body = serde_json::json!({
+            "pages": [{
+                "index": 0,
+                "markdown": "![img-0.jpeg](img-0.jpeg)",
+                "images": [{"id": "img-0.jpeg", "image_base64": BASE64.encode(b"jpeg")}],
+            }]
+        })
+        .to_string();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"include_image_base64": true}),
+            ))
+            .with_body(&body)
+            .expect(1)
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = OcrClient::builder("test-key")
+            .base_url(server.url())
+            .cache(ResultCache::open(dir.path().join("cache")))
+            .build()
+            .unwrap();
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+
+        // Image data is requested even without images, so later runs can render them.
+        let first = client
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        let options = |image_mode| OcrOptions {
+            image_mode,
+            ..Default::default()
+        };
+        let second = client
+            .process_input(&input, &options(ImageMode::Separate))
+            .unwrap();
+        let third = client
+            .process_input(&input, &options(ImageMode::Inline))
+            .unwrap();
+        client
+            .process_input(&input, &OcrOptions::default())
+            .unwrap();
+        mock.assert();
+        assert_eq!(first.pages[0].markdown, second.pages[0].markdown);
+        assert_eq!(third.pages[0].images[0].data.as_deref(), Some(&b"jpeg"[..]));
+
+        let entries = ResultCache::open(dir.path().join("cache"))
+            .entries()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source_name.as_deref(), Some("doc.pdf"));
+    }
+
+    #[test]
+    fn raw_response_is_saved_and_rendered_again() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(
+                serde_json::json!({
+                    "model": "mistral-ocr-2505",
+                    "usage_info": {"pages_processed": 1},
+                    "pages": [{
+                        "index": 0,
+                        "markdown": "![img-0.jpeg](img-0.jpeg)",
+                        "dimensions": {"dpi": 200, "height": 10, "width": 20},
+                        "images": [{"id": "img-0.jpeg", "image_base64": BASE64.encode(b"jpeg")}],
+                    }]
+                })
+                .to_string(),
+            )
+            .create();
+        let dir = tempfile::tempdir().unwrap();
+        let raw_path = dir.path().join("raw/doc.json");
+        let job = BatchJob::new(
+            OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf"),
+            dir.path().join("doc.md"),
+        )
+        .with_raw_output(&raw_path);
+        let results = BatchRunner::new(mock_client(&server)).run(&[job], &OcrOptions::default());
+        assert!(results[0].is_ok());
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&raw_path).unwrap()).unwrap();
+        assert_eq!(saved["usage_info"]["pages_processed"], 1);
+        assert_eq!(saved["pages"][0]["dimensions"]["dpi"], 200);
+
+        let document = OcrDocument::from_raw(OcrResponse::load(&raw_path).unwrap(), None).unwrap();
+        assert_eq!(document.model, "mistral-ocr-2505");
+        let options = OcrOptions {
+            image_mode: ImageMode::Separate,
+            ..Default::default()
+        };
+        let out = dir.path().join("rendered.md");
+        write_markdown(&out, &document, &options).unwrap();
+        assert_eq!(
+            fs::read(dir.path().join("rendered_images/img-0.jpeg")).unwrap(),
+            b"jpeg"
+        );
+    }
+
+    #[test]
+    fn raw_response_records_the_model_for_rendering() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(mock_response_body())
+            .create();
+        let dir = tempfile::tempdir().unwrap();
+        let raw_path = dir.path().join("doc.json");
+        let job = BatchJob::new(
+            OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf"),
+            dir.path().join("doc.md"),
+        )
+        .with_raw_output(&raw_path);
+        let results = BatchRunner::new(mock_client(&server)).run(&[job], &OcrOptions::default());
+        assert!(results[0].is_ok());
+
+        let options = OcrOptions {
+            front_matter: true,
+            ..Default::default()
+        };
+        let out = dir.path().join("rendered.md");
+        let response = OcrResponse::load(&raw_path).unwrap();
+        write_output(&out, response, SourceInfo::default(), &options).unwrap();
+        let md = fs::read_to_string(&out).unwrap();
+        assert!(
+            md.contains(&format!("model: \"{DEFAULT_MODEL}\"\n")),
+            "{md}"
+        );
+        assert!(!md.contains("source:") && !md.contains("sha256:"), "{md}");
+    }
+
+    #[test]
+    fn page_selection_is_sent_and_headers_keep_original_numbers() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"pages": [1, 9]}),
+            ))
+            .with_body(
+                serde_json::json!({
+                    "pages": [
+                        {"index": 1, "markdown": "second", "images": []},
+                        {"index": 9, "markdown": "tenth", "images": []},
+                    ]
+                })
+                .to_string(),
+            )
+            .create();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.pdf");
+        fs::write(&input, b"%PDF-1.4 fake").unwrap();
+        let out = dir.path().join("doc.md");
+        let options = OcrOptions {
+            pages: Some("2,10".parse().unwrap()),
+            ..Default::default()
+        };
+        mock_client(&server)
+            .run_ocr(&input, &options, &out)
+            .unwrap();
+
+        mock.assert();
+        let md = fs::read_to_string(&out).unwrap();
+        assert!(md.contains("# Page 2\n\nsecond"));
+        assert!(md.contains("# Page 10\n\ntenth"));
+    }
+
+    #[test]
+    fn oversized_pdf_is_split_and_stitched_back_together() {
+        let mut server = mockito::Server::new();
+        // Answer each chunk with one page per PDF page and one image per page, numbered
+        // from `img-0` within the chunk like the real API does.
+        let mock = server
+            .mock("POST", "/ocr")
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let url = body["document"]["document_url"].as_str().unwrap();
+                let pdf = decode_image_base64(url, "chunk").unwrap();
+                let count = lopdf::Document::load_mem(&pdf).unwrap().get_pages().len();
+                let pages: Vec<_> = (0..count)
+                    .map(|i| {
+                        serde_json::json!({
+                            "index": i,
+                            "markdown": format!("![img-{i}.jpeg](img-{i}.jpeg)"),
+                            "images": [{"id": format!("img-{i}.jpeg"), "image_base64": BASE64.encode(b"img")}],
+                        })
+                    })
+                    .collect();
+                serde_json::json!({ "pages": pages })
+                    .to_string()
+                    .into_bytes()
+            })
+            .expect_at_least(2)
+            .create();
+
+        let pdf = split::tests::sample_pdf(6, 10_000);
+        let client = OcrClient::builder("key")
+            .base_url(server.url())
+            .max_file_size(25_000)
+            .build()
+            .unwrap();
+        let input = OcrInput::from_bytes(pdf).with_file_name("book.pdf");
+        let options = OcrOptions {
+            image_mode: ImageMode::Separate,
+            ..Default::default()
+        };
+        let document = client.process_input(&input, &options).unwrap();
+
+        mock.assert();
+        let indices: Vec<_> = document.pages.iter().map(|p| p.index).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+        let ids: Vec<_> = document.images().map(|i| i.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "img-0.jpeg",
+                "img-1.jpeg",
+                "img-2.jpeg",
+                "img-3.jpeg",
+                "img-4.jpeg",
+                "img-5.jpeg"
+            ]
+        );
+        for page in &document.pages {
+            assert!(
+                page.markdown
+                    .ends_with(&format!("(img-{}.jpeg)", page.index))
+            );
+        }
+    }
+
+    #[test]
+    fn document_decodes_images_and_concatenates_pages() {
+        let document = sample_document();
+        let images: Vec<_> = document.images().collect();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].id, "img-0.jpeg");
+        assert_eq!(images[0].mime_type, "image/jpeg");
+        assert_eq!(images[0].data.as_deref(), Some(&b"fake-jpeg-data"[..]));
+
+        let md = document.markdown(true);
+        assert!(md.starts_with("# Page 1\n\n# Title"));
+        assert!(md.contains("![img-0.jpeg](img-0.jpeg)"));
+        assert!(md.contains("# Page 2\n\nSecond page"));
+        assert!(!document.markdown(false).contains("# Page"));
+    }
+
     #[test]
     fn decode_plain_base64() {
         let decoded = decode_image_base64(&BASE64.encode(b"hello"), "x").unwrap();
@@ -496,8 +1216,9 @@ mod tests {
         let options = OcrOptions {
             image_mode: ImageMode::Separate,
             page_headers: true,
+            ..Default::default()
         };
-        write_markdown(&out, &sample_response(), options).unwrap();
+        write_markdown(&out, &sample_document(), &options).unwrap();
 
         let md = fs::read_to_string(&out).unwrap();
         assert!(md.contains("![img-0.jpeg](doc_images/img-0.jpeg)"));
@@ -514,14 +1235,31 @@ mod tests {
         let options = OcrOptions {
             image_mode: ImageMode::None,
             page_headers: false,
+            ..Default::default()
         };
-        write_markdown(&out, &sample_response(), options).unwrap();
+        write_markdown(&out, &sample_document(), &options).unwrap();
 
         let md = fs::read_to_string(&out).unwrap();
         assert!(!md.contains("# Page"));
         assert!(md.contains("Second page"));
     }
 
+    #[test]
+    fn page_separator_template_replaces_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.md");
+        let options = OcrOptions {
+            page_separator: Some("<a id=\"page-{n}\"></a> {source}, {n}/{total}".to_string()),
+            ..Default::default()
+        };
+        write_markdown(&out, &sample_document(), &options).unwrap();
+
+        let md = fs::read_to_string(&out).unwrap();
+        assert!(!md.contains("# Page"));
+        assert!(md.starts_with("<a id=\"page-1\"></a> doc.pdf, 1/2\n\n"));
+        assert!(md.contains("<a id=\"page-2\"></a> doc.pdf, 2/2\n\nSecond page"));
+    }
+
     #[test]
     fn zip_mode_bundles_markdown_and_images() {
         let dir = tempfile::tempdir().unwrap();
@@ -529,8 +1267,9 @@ mod tests {
         let options = OcrOptions {
             image_mode: ImageMode::Zip,
             page_headers: true,
+            ..Default::default()
         };
-        write_markdown(&out, &sample_response(), options).unwrap();
+        write_markdown(&out, &sample_document(), &options).unwrap();
 
         let file = fs::File::open(dir.path().join("doc.zip")).unwrap();
         let mut archive = zip::ZipArchive::new(file).unwrap();
@@ -552,6 +1291,233 @@ mod tests {
         assert_eq!(img, b"fake-jpeg-data");
     }
 
+    #[test]
+    fn front_matter_records_source_hash_and_usage() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(
+                serde_json::json!({
+                    "model": "mistral-ocr-2505",
+                    "usage_info": {"pages_processed": 1, "doc_size_bytes": 8},
+                    "pages": [{"index": 0, "markdown": "Body", "images": []}]
+                })
+                .to_string(),
+            )
+            .create();
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.md");
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+        let options = OcrOptions {
+            front_matter: true,
+            ..Default::default()
+        };
+        mock_client(&server)
+            .run_ocr_input(&input, &options, &out)
+            .unwrap();
+
+        let md = fs::read_to_string(&out).unwrap();
+        let sha = cache::to_hex(&<sha2::Sha256 as sha2::Digest>::digest(b"%PDF-1.4"));
+        assert!(md.starts_with("---\nsource: \"doc.pdf\"\n"));
+        assert!(md.contains(&format!("sha256: {sha}\n")));
+        assert!(md.contains("model: \"mistral-ocr-2505\"\n"));
+        assert!(md.contains("  pages_processed: 1\n  doc_size_bytes: 8\n---\n\nBody\n"));
+    }
+
+    #[test]
+    fn annotations_are_requested_and_written_to_a_sidecar() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "document_annotation_format": {
+                    "type": "json_schema",
+                    "json_schema": {"name": "invoice", "schema": {"type": "object"}},
+                },
+                "bbox_annotation_format": {"json_schema": {"name": "figure"}},
+            })))
+            .with_body(
+                serde_json::json!({
+                    "document_annotation": "{\"total\": 42.5}",
+                    "pages": [{
+                        "index": 0,
+                        "markdown": "![img-0.jpeg](img-0.jpeg)",
+                        "images": [
+                            {"id": "img-0.jpeg", "image_annotation": "{\"kind\": \"logo\"}"},
+                            {"id": "img-1.jpeg"},
+                        ],
+                    }],
+                })
+                .to_string(),
+            )
+            .create();
+        let options = OcrOptions {
+            document_annotation_format: Some(AnnotationSchema::new(
+                "invoice",
+                serde_json::json!({"type": "object"}),
+            )),
+            bbox_annotation_format: Some(AnnotationSchema::new(
+                "figure",
+                serde_json::json!({"type": "object"}),
+            )),
+            ..Default::default()
+        };
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("inv.pdf");
+        let client = mock_client(&server);
+        let document = client.process_input(&input, &options).unwrap();
+        mock.assert();
+        assert_eq!(
+            document.document_annotation,
+            Some(serde_json::json!({"total": 42.5}))
+        );
+        assert_eq!(
+            document.pages[0].images[0].annotation,
+            Some(serde_json::json!({"kind": "logo"}))
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("inv.md");
+        client.run_ocr_input(&input, &options, &out).unwrap();
+        let sidecar: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(annotations_path(&out)).unwrap()).unwrap();
+        assert_eq!(
+            sidecar,
+            serde_json::json!({
+                "document_annotation": {"total": 42.5},
+                "images": [{"page_index": 0, "id": "img-0.jpeg", "annotation": {"kind": "logo"}}],
+            })
+        );
+        assert!(fs::read_to_string(&out).unwrap().contains("img-0.jpeg"));
+    }
+
+    #[test]
+    fn running_headers_are_extracted_and_stripped() {
+        let mut server = mockito::Server::new();
+        // Like the API, only move the header out of the markdown when asked to.
+        let mock = server
+            .mock("POST", "/ocr")
+            .with_body_from_request(|request| {
+                let extract = request
+                    .utf8_lossy_body()
+                    .unwrap()
+                    .contains("extract_header");
+                let pages: Vec<_> = ["Intro", "Methods", "Results"]
+                    .iter()
+                    .enumerate()
+                    .map(|(n, body)| {
+                        let header = if extract { "" } else { "ACME Corp\n\n" };
+                        serde_json::json!({
+                            "index": n,
+                            "markdown": format!("{header}{body}\n\n- {}/3 -", n + 1),
+                            "header": extract.then_some("ACME Corp"),
+                            "images": [],
+                        })
+                    })
+                    .collect();
+                serde_json::json!({ "pages": pages }).to_string().into()
+            })
+            .expect(4)
+            .create();
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.md");
+        let input = OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("doc.pdf");
+        let client = mock_client(&server);
+        for extract_headers in [false, true] {
+            let options = OcrOptions {
+                strip_headers: true,
+                extract_headers,
+                page_headers: false,
+                ..Default::default()
+            };
+            let document = client.process_input(&input, &options).unwrap();
+            assert_eq!(
+                document.pages[0].header.as_deref(),
+                extract_headers.then_some("ACME Corp")
+            );
+            client.run_ocr_input(&input, &options, &out).unwrap();
+            assert_eq!(
+                fs::read_to_string(&out).unwrap(),
+                "Intro\n\nMethods\n\nResults\n\n"
+            );
+        }
+        mock.assert();
+    }
+
+    #[test]
+    fn per_page_files_get_their_own_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.md");
+        let options = OcrOptions {
+            image_mode: ImageMode::Separate,
+            per_page: true,
+            ..Default::default()
+        };
+        write_markdown(&out, &sample_document(), &options).unwrap();
+        assert_eq!(options.written_path(&out), dir.path().join("doc"));
+
+        let first = fs::read_to_string(dir.path().join("doc/page-001.md")).unwrap();
+        assert_eq!(
+            first,
+            "# Title\n\n![img-0.jpeg](page-001_images/img-0.jpeg)\n"
+        );
+        assert!(dir.path().join("doc/page-001_images/img-0.jpeg").exists());
+        let second = fs::read_to_string(dir.path().join("doc/page-002.md")).unwrap();
+        assert_eq!(second, "Second page\n");
+        assert!(!out.exists());
+
+        let options = OcrOptions {
+            image_mode: ImageMode::Zip,
+            ..options
+        };
+        write_markdown(&out, &sample_document(), &options).unwrap();
+        let file = fs::File::open(dir.path().join("doc.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, ["images/img-0.jpeg", "page-001.md", "page-002.md"]);
+        let mut md = String::new();
+        archive
+            .by_name("page-001.md")
+            .unwrap()
+            .read_to_string(&mut md)
+            .unwrap();
+        assert!(md.contains("](images/img-0.jpeg)"));
+    }
+
+    #[test]
+    fn json_output_follows_image_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.json");
+        let options = OcrOptions {
+            format: OutputFormat::Json,
+            image_mode: ImageMode::Separate,
+            ..Default::default()
+        };
+        write_output(&out, sample_response(), SourceInfo::default(), &options).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+        let page = &json["pages"][0];
+        assert_eq!(
+            page["markdown"],
+            "# Title\n\n![img-0.jpeg](doc_images/img-0.jpeg)"
+        );
+        assert_eq!(page["images"][0]["id"], "img-0.jpeg");
+        assert!(page["images"][0].get("image_base64").is_none());
+        assert_eq!(
+            fs::read(dir.path().join("doc_images/img-0.jpeg")).unwrap(),
+            b"fake-jpeg-data"
+        );
+
+        let options = OcrOptions {
+            image_mode: ImageMode::Inline,
+            ..options
+        };
+        write_json(&out, &sample_response(), &options).unwrap();
+        let saved = OcrResponse::load(&out).unwrap();
+        assert_eq!(saved, sample_response());
+    }
+
     #[test]
     fn image_without_data_keeps_original_link() {
         let dir = tempfile::tempdir().unwrap();
@@ -563,14 +1529,19 @@ mod tests {
                 images: vec![OcrImage {
                     id: Some("img-0.jpeg".to_string()),
                     image_base64: None,
+                    ..Default::default()
                 }],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let options = OcrOptions {
             image_mode: ImageMode::Separate,
             page_headers: true,
+            ..Default::default()
         };
-        write_markdown(&out, &response, options).unwrap();
+        let document = OcrDocument::from_response(response, None, DEFAULT_MODEL).unwrap();
+        write_markdown(&out, &document, &options).unwrap();
 
         let md = fs::read_to_string(&out).unwrap();
         assert!(md.contains("![img-0.jpeg](img-0.jpeg)"));
@@ -1,7 +1,13 @@
-use clap::{Parser, ValueEnum};
-use mistral_ocr::{ImageMode, OcrOptions};
-use std::path::PathBuf;
-use tracing::error;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mistral_ocr::{
+    AnnotationSchema, BatchJob, BatchRunner, BatchState, CONVERTIBLE_EXTENSIONS, DEFAULT_BASE_URL,
+    DEFAULT_MODEL, IMAGE_EXTENSIONS, ImageMode, OcrClient, OcrClientBuilder, OcrInput, OcrOptions,
+    OcrResponse, OutputFormat, PageSelection, ResultCache, SourceInfo, TableFormat, UploadMode,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info};
 
 fn get_api_key() -> String {
     std::env::var("MISTRAL_API_KEY").unwrap_or_else(|_| {
@@ -11,23 +17,411 @@ fn get_api_key() -> String {
 }
 
 #[derive(Parser)]
-#[command(about = "Run Mistral OCR on a PDF, image, or document file")]
+#[command(
+    about = "Run Mistral OCR on PDF, image, or document files",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
-    /// Path to the input file (PDF, image, or document: docx, odt, pptx, xlsx, etc.)
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Process documents as an asynchronous Mistral batch job instead of one request
+    /// per document; suited to large offline backlogs
+    #[command(subcommand)]
+    Batch(BatchCommand),
+
+    /// Inspect or clean up the cache of OCR results
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// Render a response saved with --save-raw again, without calling the API
+    Render(RenderArgs),
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List cached results
+    Ls(CacheDirArgs),
+    /// Remove all cached results
+    Clear(CacheDirArgs),
+    /// Remove cached results older than a number of days
+    Prune {
+        #[command(flatten)]
+        dir: CacheDirArgs,
+
+        /// Remove results cached more than this many days ago
+        #[arg(long, default_value_t = 30)]
+        max_age_days: u64,
+    },
+}
+
+#[derive(Args)]
+struct CacheDirArgs {
+    /// Cache directory (default: the platform cache directory, e.g. ~/.cache/mistral_ocr)
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    /// Submit documents as a batch job, save its state file and wait for the results
+    Submit(Box<BatchSubmitArgs>),
+    /// Show the status of a submitted batch job
+    Status(BatchStateArgs),
+    /// Wait for a submitted batch job, e.g. after an interruption, and write its results
+    Resume(BatchStateArgs),
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Input files (PDF, image, or document: docx, odt, pptx, xlsx, etc.), directories
+    /// (searched recursively), glob patterns such as `scans/**/*.pdf`, or http(s) URLs
+    /// that the API fetches directly
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Where to write the output (.md, .json or .html file, or .zip when --images zip)
+    /// for a single input. Defaults to the input file name with the format's extension (in
+    /// the current directory for URLs).
+    #[arg(long, conflicts_with = "output_dir")]
+    output: Option<PathBuf>,
+
+    /// Write outputs under this directory, mirroring the layout of input directories
+    /// and glob matches, instead of next to each input
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+
+    /// Only OCR these pages of a PDF, e.g. `1-5,10` (1-based)
+    #[arg(long)]
+    pages: Option<PageSelection>,
+
+    /// JSON Schema file for the API to fill in from the whole document, e.g. invoice
+    /// fields; the result is written to a `.annotations.json` file next to the output
+    #[arg(long, value_name = "FILE")]
+    document_annotation_schema: Option<PathBuf>,
+
+    /// JSON Schema file for the API to fill in for each extracted image; the results are
+    /// written to a `.annotations.json` file next to the output
+    #[arg(long, value_name = "FILE")]
+    bbox_annotation_schema: Option<PathBuf>,
+
+    /// Ask the API to move running headers and footers out of the page text, for models
+    /// that support it (others reject the request); see also --strip-headers
+    #[arg(long)]
+    extract_headers: bool,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// Output format: markdown, json (pages with markdown, image ids and bounding boxes,
+    /// page dimensions and usage info), html (standalone page for a browser; math is
+    /// rendered by MathJax from a CDN, so needs network access), text (markdown syntax and
+    /// images stripped)
+    #[arg(long, value_enum, default_value_t = CliFormat::Markdown)]
+    format: CliFormat,
 
     /// How to handle images: none, separate (save to _images/ dir), inline (embed base64 in markdown), zip (bundle md + images into a .zip)
     #[arg(long, value_enum, default_value_t = CliImageMode::None)]
     images: CliImageMode,
 
-    /// Where to write the output (.md file, or .zip when --images zip).
-    /// Defaults to the input file name with an .md extension.
+    /// Do not insert `# Page N` headers between pages of multi-page documents
+    #[arg(long)]
+    no_page_headers: bool,
+
+    /// Template for the header before each page instead of `# Page N`, e.g.
+    /// `<!-- page {n} -->` or `## Page {n} of {total}`; `{source}` is the input file name
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "no_page_headers")]
+    page_separator: Option<String>,
+
+    /// Write the pages as one continuous text: rejoin paragraphs and hyphenated words split
+    /// at page breaks, and mark pages with inline `<a id="page-N"></a>` anchors instead of
+    /// headers
+    #[arg(long, conflicts_with = "per_page")]
+    continuous: bool,
+
+    /// Write one markdown file per page into a directory named after the output, e.g.
+    /// `doc/page-001.md` (with --images zip, all pages go into one zip)
+    #[arg(long)]
+    per_page: bool,
+
+    /// Start markdown with YAML front matter: source file name and SHA-256, page count,
+    /// model, processing time, tool version and API usage
+    #[arg(long)]
+    front_matter: bool,
+
+    /// Also write the tables found in the pages next to the output: csv (one file per
+    /// table in a _tables/ dir), xlsx (one workbook with a sheet per table). Tables
+    /// continuing across pages under a repeated header row are merged
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    tables: Option<CliTableFormat>,
+
+    /// Remove running headers, footers and page numbers repeated across most pages
+    #[arg(long)]
+    strip_headers: bool,
+
+    /// With --format html, do not load MathJax from its CDN; math is shown as TeX
+    #[arg(long)]
+    no_mathjax: bool,
+}
+
+#[derive(Args)]
+struct ApiArgs {
+    /// Mistral API base URL, e.g. a proxy in front of the API
+    #[arg(long, default_value = DEFAULT_BASE_URL)]
+    base_url: String,
+
+    /// Limit API requests per minute, e.g. to stay under the account's rate limit
+    #[arg(long)]
+    requests_per_minute: Option<u32>,
+}
+
+#[derive(Args)]
+struct RunArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    api: ApiArgs,
+
+    /// OCR model to use, e.g. a pinned version instead of the latest one
+    #[arg(long, default_value = DEFAULT_MODEL)]
+    model: String,
+
+    /// How to send the document: inline (base64 in the request), files (upload via the Files API and pass a signed URL)
+    #[arg(long, value_enum, default_value_t = CliUpload::Inline)]
+    upload: CliUpload,
+
+    /// With --upload files, keep the uploaded file instead of deleting it afterwards
+    #[arg(long)]
+    keep_uploads: bool,
+
+    /// For URL inputs, download the document locally first instead of letting the API
+    /// fetch it (for URLs the API cannot reach)
+    #[arg(long)]
+    download: bool,
+
+    /// Number of documents to process at the same time
+    #[arg(long, short = 'j', default_value_t = 4)]
+    concurrency: usize,
+
+    /// Reuse OCR results cached by earlier runs and cache new ones (implied by
+    /// --cache-dir)
+    #[arg(long)]
+    cache: bool,
+
+    #[command(flatten)]
+    cache_dir: CacheDirArgs,
+
+    /// Also save the raw API response for a single input as JSON, to render it again
+    /// later with `mistral_ocr render`. Pass --images to keep image data in it.
+    #[arg(long)]
+    save_raw: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Response JSON saved with --save-raw
+    response: PathBuf,
+
+    /// Where to write the output (.md, .json or .html file, or .zip when --images zip). Defaults
+    /// to the response file name with the format's extension.
     #[arg(long)]
     output: Option<PathBuf>,
 
-    /// Do not insert `# Page N` headers between pages of multi-page documents
+    #[command(flatten)]
+    output_options: OutputArgs,
+}
+
+#[derive(Args)]
+struct BatchSubmitArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    api: ApiArgs,
+
+    /// OCR model to use, e.g. a pinned version instead of the latest one
+    #[arg(long, default_value = DEFAULT_MODEL)]
+    model: String,
+
+    /// Where to save the job state used by `batch status` and `batch resume`
+    #[arg(long, default_value = DEFAULT_BATCH_STATE)]
+    state: PathBuf,
+
+    /// Keep the uploaded request file and the result files in the account instead of
+    /// deleting them once the results are written
     #[arg(long)]
-    no_page_headers: bool,
+    keep_uploads: bool,
+
+    /// Exit after submitting instead of waiting for the results
+    #[arg(long)]
+    no_wait: bool,
+
+    /// Seconds between status checks while waiting
+    #[arg(long, default_value_t = 30)]
+    poll_interval: u64,
+}
+
+#[derive(Args)]
+struct BatchStateArgs {
+    /// Job state file written by `batch submit`
+    #[arg(long, default_value = DEFAULT_BATCH_STATE)]
+    state: PathBuf,
+
+    #[command(flatten)]
+    api: ApiArgs,
+
+    /// Seconds between status checks while waiting
+    #[arg(long, default_value_t = 30)]
+    poll_interval: u64,
+}
+
+const DEFAULT_BATCH_STATE: &str = "mistral_ocr_batch.json";
+
+impl InputArgs {
+    fn options(&self, output: &OutputArgs) -> Result<OcrOptions, String> {
+        let load = |path: &Option<PathBuf>| {
+            path.as_deref()
+                .map(AnnotationSchema::load)
+                .transpose()
+                .map_err(|e| format!("{e:#}"))
+        };
+        Ok(OcrOptions {
+            document_annotation_format: load(&self.document_annotation_schema)?,
+            bbox_annotation_format: load(&self.bbox_annotation_schema)?,
+            extract_headers: self.extract_headers,
+            ..output.options(self.pages.clone())?
+        })
+    }
+}
+
+impl OutputArgs {
+    fn options(&self, pages: Option<PageSelection>) -> Result<OcrOptions, String> {
+        if self.format != CliFormat::Markdown {
+            if self.per_page {
+                return Err("--per-page only applies to --format markdown".into());
+            }
+            if self.front_matter {
+                return Err("--front-matter only applies to --format markdown".into());
+            }
+            if self.page_separator.is_some() {
+                return Err("--page-separator only applies to --format markdown".into());
+            }
+            if self.continuous {
+                return Err("--continuous only applies to --format markdown".into());
+            }
+        }
+        if self.no_mathjax && self.format != CliFormat::Html {
+            return Err("--no-mathjax only applies to --format html".into());
+        }
+        if self.strip_headers && self.format == CliFormat::Json {
+            return Err("--strip-headers does not apply to --format json".into());
+        }
+        Ok(OcrOptions {
+            format: self.format.into(),
+            image_mode: self.images.into(),
+            page_headers: !self.no_page_headers,
+            page_separator: self.page_separator.clone(),
+            continuous: self.continuous,
+            pages,
+            per_page: self.per_page,
+            front_matter: self.front_matter,
+            tables: self.tables.map(Into::into),
+            strip_headers: self.strip_headers,
+            mathjax: !self.no_mathjax,
+            ..Default::default()
+        })
+    }
+}
+
+impl RunArgs {
+    fn upload_mode(&self) -> Result<UploadMode, String> {
+        match self.upload {
+            CliUpload::Inline if self.keep_uploads => {
+                Err("--keep-uploads only applies to --upload files".into())
+            }
+            CliUpload::Inline => Ok(UploadMode::Inline),
+            CliUpload::Files => Ok(UploadMode::Files {
+                delete_after: !self.keep_uploads,
+            }),
+        }
+    }
+}
+
+impl CacheDirArgs {
+    fn open(&self) -> Result<ResultCache, String> {
+        match &self.cache_dir {
+            Some(dir) => Ok(ResultCache::open(dir)),
+            None => ResultCache::open_default().map_err(|e| format!("{e:#}")),
+        }
+    }
+}
+
+impl ApiArgs {
+    fn builder(&self) -> OcrClientBuilder {
+        let builder = OcrClient::builder(get_api_key()).base_url(&self.base_url);
+        match self.requests_per_minute {
+            Some(rpm) => builder.requests_per_minute(rpm),
+            None => builder,
+        }
+    }
+}
+
+fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliUpload {
+    Inline,
+    Files,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliFormat {
+    Markdown,
+    Json,
+    Html,
+    Text,
+}
+
+impl From<CliFormat> for OutputFormat {
+    fn from(f: CliFormat) -> Self {
+        match f {
+            CliFormat::Markdown => OutputFormat::Markdown,
+            CliFormat::Json => OutputFormat::Json,
+            CliFormat::Html => OutputFormat::Html,
+            CliFormat::Text => OutputFormat::Text,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliTableFormat {
+    Csv,
+    Xlsx,
+}
+
+impl From<CliTableFormat> for TableFormat {
+    fn from(f: CliTableFormat) -> Self {
+        match f {
+            CliTableFormat::Csv => TableFormat::Csv,
+            CliTableFormat::Xlsx => TableFormat::Xlsx,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -49,13 +443,175 @@ impl From<CliImageMode> for ImageMode {
     }
 }
 
+/// One document to OCR and where its output goes.
+#[derive(Debug, PartialEq, Eq)]
+struct Job {
+    input: String,
+    output: PathBuf,
+}
+
+/// Expand files, directories, glob patterns and URLs into jobs.
+///
+/// Directories and patterns only pick up supported file types; explicitly named files
+/// are passed through so unsupported types are reported as failures. With `output_dir`,
+/// outputs keep their path relative to the directory or pattern base they came from.
+fn collect_jobs(
+    inputs: &[String],
+    output: Option<&Path>,
+    output_dir: Option<&Path>,
+    extension: &str,
+) -> Result<Vec<Job>, String> {
+    let mut jobs = Vec::new();
+    for input in inputs {
+        // (input, path relative to its root, default output location)
+        let mut found: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+        let path = Path::new(input);
+        if is_url(input) {
+            let name = url_output_name(
+                OcrInput::from_url(input).file_name().unwrap_or("document"),
+                extension,
+            );
+            found.push((input.clone(), name.clone(), name));
+        } else if path.is_dir() {
+            let mut files = Vec::new();
+            walk_dir(path, &mut files)
+                .map_err(|e| format!("Failed to read directory {input}: {e}"))?;
+            if files.is_empty() {
+                return Err(format!("No supported files found in {input}"));
+            }
+            for file in files {
+                let rel = file.strip_prefix(path).unwrap_or(&file).to_path_buf();
+                found.push((file.to_string_lossy().into_owned(), rel, file));
+            }
+        } else if !path.exists() && input.contains(['*', '?', '[']) {
+            let base = glob_base(input);
+            let matches = glob::glob(input).map_err(|e| format!("Invalid pattern {input}: {e}"))?;
+            for file in matches.filter_map(|m| m.ok()) {
+                if file.is_file() && is_supported(&file) {
+                    let rel = file.strip_prefix(&base).unwrap_or(&file).to_path_buf();
+                    found.push((file.to_string_lossy().into_owned(), rel, file));
+                }
+            }
+            if found.is_empty() {
+                return Err(format!("No supported files match {input}"));
+            }
+        } else {
+            let name = path.file_name().map(PathBuf::from).unwrap_or_default();
+            found.push((input.clone(), name, path.to_path_buf()));
+        }
+        if !is_url(input) {
+            for (_, rel, default) in &mut found {
+                rel.set_extension(extension);
+                default.set_extension(extension);
+            }
+        }
+
+        for (input, rel, default) in found {
+            let output = match output_dir {
+                Some(dir) => dir.join(rel),
+                None => default,
+            };
+            jobs.push(Job { input, output });
+        }
+    }
+
+    disambiguate_outputs(&mut jobs, extension)?;
+
+    match (output, jobs.as_mut_slice()) {
+        (None, _) => {}
+        (Some(output), [job]) => job.output = output.to_path_buf(),
+        (Some(_), _) => {
+            return Err("--output needs exactly one input; use --output-dir for several".into());
+        }
+    }
+    Ok(jobs)
+}
+
+/// Name the outputs of jobs that would otherwise write to the same file, such as those
+/// for `scan.pdf` and `scan.png`, after the whole input file name, e.g. `scan.pdf.md`.
+/// Fails if outputs still collide, e.g. for the same input given twice.
+fn disambiguate_outputs(jobs: &mut [Job], extension: &str) -> Result<(), String> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for job in jobs.iter() {
+        *counts.entry(job.output.clone()).or_default() += 1;
+    }
+    for job in jobs.iter_mut().filter(|job| counts[&job.output] > 1) {
+        let name = if is_url(&job.input) {
+            OcrInput::from_url(&job.input)
+                .file_name()
+                .map(str::to_string)
+        } else {
+            Path::new(&job.input)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        };
+        if let Some(name) = name {
+            job.output.set_file_name(format!("{name}.{extension}"));
+        }
+    }
+
+    let mut seen: HashMap<&Path, &str> = HashMap::new();
+    for job in jobs.iter() {
+        if let Some(other) = seen.insert(&job.output, &job.input) {
+            return Err(format!(
+                "{other} and {} would both be written to {}",
+                job.input,
+                job.output.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Output file name for a document fetched from a URL: `name` with `extension` appended.
+/// Only a known document extension is replaced, since names taken from URLs often contain
+/// dots of their own, e.g. `2401.00001` from an arXiv link.
+fn url_output_name(name: &str, extension: &str) -> PathBuf {
+    let name = Path::new(name);
+    let stem = match name.file_stem() {
+        Some(stem) if is_supported(name) => stem,
+        _ => name.as_os_str(),
+    };
+    let mut name = stem.to_os_string();
+    name.push(format!(".{extension}"));
+    PathBuf::from(name)
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk_dir(&path, files)?;
+        } else if is_supported(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_supported(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    ext == "pdf"
+        || IMAGE_EXTENSIONS.contains(&ext.as_str())
+        || CONVERTIBLE_EXTENSIONS.contains(&ext.as_str())
+}
+
+/// Leading components of a glob pattern that contain no wildcards.
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+        .collect()
+}
+
 fn main() {
     let cli = Cli::parse();
-    let options = OcrOptions {
-        image_mode: cli.images.into(),
-        page_headers: !cli.no_page_headers,
-    };
-    let output = cli.output.unwrap_or_else(|| cli.input.with_extension("md"));
 
     tracing_subscriber::fmt()
         .with_target(false)
@@ -68,9 +624,408 @@ fn main() {
         )
         .init();
 
-    let api_key = get_api_key();
-    if let Err(err) = mistral_ocr::run_ocr(&cli.input, options, &output, &api_key) {
-        error!("{err:#}");
-        std::process::exit(1);
+    let result = match cli.command {
+        None => run(cli.run),
+        Some(Command::Batch(BatchCommand::Submit(args))) => batch_submit(*args),
+        Some(Command::Batch(BatchCommand::Status(args))) => batch_status(args),
+        Some(Command::Batch(BatchCommand::Resume(args))) => batch_resume(args),
+        Some(Command::Cache(command)) => cache(command),
+        Some(Command::Render(args)) => render(args),
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            error!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Result of a command: `Ok(false)` when some documents failed and were already
+/// reported, `Err` for errors that stopped the command as a whole.
+type CommandResult = Result<bool, String>;
+
+fn to_batch_jobs(jobs: &[Job]) -> Vec<BatchJob<'static>> {
+    jobs.iter()
+        .map(|job| {
+            let input = if is_url(&job.input) {
+                OcrInput::from_url(&job.input)
+            } else {
+                OcrInput::from_path(&job.input)
+            };
+            BatchJob::new(input, &job.output)
+        })
+        .collect()
+}
+
+fn run(args: RunArgs) -> CommandResult {
+    let options = args.input.options(&args.output)?;
+    let jobs = collect_jobs(
+        &args.input.inputs,
+        args.input.output.as_deref(),
+        args.input.output_dir.as_deref(),
+        options.format.extension(),
+    )?;
+
+    let upload = args.upload_mode()?;
+    let mut builder = args
+        .api
+        .builder()
+        .model(args.model)
+        .upload_mode(upload)
+        .download_urls(args.download);
+    if args.cache || args.cache_dir.cache_dir.is_some() {
+        builder = builder.cache(args.cache_dir.open()?);
+    }
+    let client = builder.build().map_err(|e| format!("{e:#}"))?;
+
+    let mut batch_jobs = to_batch_jobs(&jobs);
+    match (&args.save_raw, batch_jobs.as_mut_slice()) {
+        (None, _) => {}
+        (Some(path), [job]) => job.raw_output = Some(path.clone()),
+        (Some(_), _) => return Err("--save-raw needs exactly one input".into()),
+    }
+
+    let names: Vec<String> = jobs.iter().map(|job| job.input.clone()).collect();
+    let results = BatchRunner::new(client)
+        .concurrency(args.concurrency)
+        .on_progress(move |p| match p.error {
+            Some(err) if p.total > 1 => {
+                error!("[{}/{}] {}: {err:#}", p.completed, p.total, names[p.index])
+            }
+            Some(err) => error!("{err:#}"),
+            None if p.total > 1 => info!("[{}/{}] {} done", p.completed, p.total, names[p.index]),
+            None => {}
+        })
+        .run(&batch_jobs, &options);
+    let names: Vec<_> = jobs.iter().map(|job| job.input.as_str()).collect();
+    Ok(summarize(&names, &results))
+}
+
+fn batch_submit(args: BatchSubmitArgs) -> CommandResult {
+    let options = args.input.options(&args.output)?;
+    let jobs = collect_jobs(
+        &args.input.inputs,
+        args.input.output.as_deref(),
+        args.input.output_dir.as_deref(),
+        options.format.extension(),
+    )?;
+    let mut builder = args.api.builder().model(args.model);
+    if args.keep_uploads {
+        builder = builder.upload_mode(UploadMode::Files {
+            delete_after: false,
+        });
+    }
+    let client = builder.build().map_err(|e| format!("{e:#}"))?;
+
+    let state = client
+        .submit_batch(&to_batch_jobs(&jobs), &options)
+        .map_err(|e| format!("{e:#}"))?;
+    state.save(&args.state).map_err(|e| format!("{e:#}"))?;
+    info!(
+        "Saved batch state to {}; check on it with `mistral_ocr batch status --state {0}`",
+        args.state.display()
+    );
+    if args.no_wait {
+        return Ok(true);
+    }
+    wait_and_collect(&client, &state, args.poll_interval)
+}
+
+fn batch_status(args: BatchStateArgs) -> CommandResult {
+    let state = BatchState::load(&args.state).map_err(|e| format!("{e:#}"))?;
+    let client = args.api.builder().build().map_err(|e| format!("{e:#}"))?;
+    let status = client.batch_status(&state).map_err(|e| format!("{e:#}"))?;
+    info!(
+        "Batch job {}: {:?}, {}/{} requests done ({} failed)",
+        state.job_id,
+        status.status,
+        status.succeeded_requests + status.failed_requests,
+        status.total_requests,
+        status.failed_requests
+    );
+    Ok(true)
+}
+
+fn batch_resume(args: BatchStateArgs) -> CommandResult {
+    let state = BatchState::load(&args.state).map_err(|e| format!("{e:#}"))?;
+    let client = args.api.builder().build().map_err(|e| format!("{e:#}"))?;
+    wait_and_collect(&client, &state, args.poll_interval)
+}
+
+fn wait_and_collect(client: &OcrClient, state: &BatchState, poll_interval: u64) -> CommandResult {
+    let status = client
+        .wait_for_batch(state, Duration::from_secs(poll_interval))
+        .map_err(|e| format!("{e:#}"))?;
+    let results = client
+        .collect_batch(state, &status)
+        .map_err(|e| format!("{e:#}"))?;
+    let names: Vec<_> = state
+        .entries
+        .iter()
+        .map(|e| e.name.as_deref().unwrap_or("document"))
+        .collect();
+    for (name, result) in names.iter().zip(&results) {
+        if let Err(err) = result {
+            error!("{name}: {err:#}");
+        }
+    }
+    Ok(summarize(&names, &results))
+}
+
+fn render(args: RenderArgs) -> CommandResult {
+    let options = args.output_options.options(None)?;
+    let output = args
+        .output
+        .unwrap_or_else(|| args.response.with_extension(options.format.extension()));
+    if output == args.response {
+        return Err("Output would overwrite the response; pass --output".into());
+    }
+    let response = OcrResponse::load(&args.response).map_err(|e| format!("{e:#}"))?;
+    // The response does not record the source document, so front matter leaves out its
+    // name and hash; the model comes from the response.
+    mistral_ocr::write_output(&output, response, SourceInfo::default(), &options)
+        .map_err(|e| format!("{e:#}"))?;
+    let written = options.written_path(&output);
+    info!("Done! Output written to {}", written.display());
+    Ok(true)
+}
+
+fn cache(command: CacheCommand) -> CommandResult {
+    match command {
+        CacheCommand::Ls(dir) => {
+            let entries = dir.open()?.entries().map_err(|e| format!("{e:#}"))?;
+            let mut total = 0;
+            for entry in &entries {
+                let age = entry.created.elapsed().unwrap_or_default();
+                println!(
+                    "{}  {:>9}  {:>5}  {:<20}  {}",
+                    entry.key.get(..12).unwrap_or(&entry.key),
+                    format_size(entry.size),
+                    format_age(age),
+                    entry.model,
+                    entry.source_name.as_deref().unwrap_or("-"),
+                );
+                total += entry.size;
+            }
+            println!("{} entries, {}", entries.len(), format_size(total));
+        }
+        CacheCommand::Clear(dir) => {
+            let removed = dir.open()?.clear().map_err(|e| format!("{e:#}"))?;
+            info!("Removed {removed} cached results");
+        }
+        CacheCommand::Prune { dir, max_age_days } => {
+            let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+            let removed = dir.open()?.prune(max_age).map_err(|e| format!("{e:#}"))?;
+            info!("Removed {removed} cached results older than {max_age_days} days");
+        }
+    }
+    Ok(true)
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// Log a per-file summary for multi-file runs; returns whether every file succeeded.
+fn summarize<T>(names: &[&str], results: &[mistral_ocr::Result<T>]) -> bool {
+    let failures: Vec<_> = names
+        .iter()
+        .zip(results)
+        .filter_map(|(name, result)| result.as_ref().err().map(|err| (name, err)))
+        .collect();
+    if names.len() > 1 {
+        info!(
+            "Processed {} files: {} succeeded, {} failed",
+            names.len(),
+            names.len() - failures.len(),
+            failures.len()
+        );
+        for (name, err) in &failures {
+            error!("  {name}: {err}");
+        }
+    }
+    failures.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+
+    #[test]
+    fn directories_are_walked_and_mirrored_into_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let scans = dir.path().join("scans");
+        touch(&scans.join("a.pdf"));
+        touch(&scans.join("2024/b.PNG"));
+        touch(&scans.join("2024/notes.md"));
+
+        let out = dir.path().join("out");
+        let jobs = collect_jobs(
+            &[scans.to_string_lossy().into_owned()],
+            None,
+            Some(&out),
+            "md",
+        )
+        .unwrap();
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(outputs, [out.join("2024/b.md"), out.join("a.md")]);
+
+        let jobs = collect_jobs(&[scans.to_string_lossy().into_owned()], None, None, "md").unwrap();
+        assert_eq!(jobs[1].output, scans.join("a.md"));
+    }
+
+    #[test]
+    fn glob_matches_are_relative_to_pattern_base() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("in/x/one.pdf"));
+        touch(&dir.path().join("in/y/two.pdf"));
+        touch(&dir.path().join("in/y/skip.txt.bak"));
+
+        let pattern = format!("{}/in/**/*", dir.path().display());
+        let out = dir.path().join("out");
+        let jobs = collect_jobs(&[pattern], None, Some(&out), "md").unwrap();
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(outputs, [out.join("x/one.md"), out.join("y/two.md")]);
+    }
+
+    #[test]
+    fn colliding_outputs_keep_the_source_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("scan.pdf"));
+        touch(&dir.path().join("scan.png"));
+        touch(&dir.path().join("other.pdf"));
+
+        let jobs = collect_jobs(
+            &[dir.path().to_string_lossy().into_owned()],
+            None,
+            None,
+            "md",
+        )
+        .unwrap();
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(
+            outputs,
+            [
+                dir.path().join("other.md"),
+                dir.path().join("scan.pdf.md"),
+                dir.path().join("scan.png.md"),
+            ]
+        );
+
+        let err = collect_jobs(
+            &[
+                "https://a.example/download".into(),
+                "https://b.example/download".into(),
+            ],
+            None,
+            None,
+            "md",
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("would both be written to download.md"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn single_output_needs_single_input() {
+        let jobs = collect_jobs(&["a.pdf".into()], Some(Path::new("x.md")), None, "md").unwrap();
+        assert_eq!(jobs[0].output, Path::new("x.md"));
+        assert!(
+            collect_jobs(
+                &["a.pdf".into(), "b.pdf".into()],
+                Some(Path::new("x.md")),
+                None,
+                "md"
+            )
+            .is_err()
+        );
+
+        let jobs = collect_jobs(
+            &["https://example.com/paper.pdf".into()],
+            None,
+            None,
+            "json",
+        )
+        .unwrap();
+        assert_eq!(jobs[0].output, Path::new("paper.json"));
+
+        let jobs = collect_jobs(
+            &[
+                "https://arxiv.org/pdf/2401.00001".into(),
+                "https://arxiv.org/pdf/2401.00002v2".into(),
+            ],
+            None,
+            None,
+            "md",
+        )
+        .unwrap();
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(
+            outputs,
+            [Path::new("2401.00001.md"), Path::new("2401.00002v2.md")]
+        );
+    }
+
+    #[test]
+    fn plain_inputs_and_batch_subcommand_both_parse() {
+        let cli = Cli::try_parse_from(["mistral_ocr", "a.pdf", "--images", "zip"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.run.input.inputs, ["a.pdf"]);
+
+        let cli =
+            Cli::try_parse_from(["mistral_ocr", "batch", "resume", "--state", "s.json"]).unwrap();
+        let Some(Command::Batch(BatchCommand::Resume(args))) = cli.command else {
+            panic!("expected batch resume");
+        };
+        assert_eq!(args.state, Path::new("s.json"));
+
+        let cli = Cli::try_parse_from(["mistral_ocr", "render", "raw.json", "--images", "inline"])
+            .unwrap();
+        let Some(Command::Render(args)) = cli.command else {
+            panic!("expected render");
+        };
+        assert_eq!(args.response, Path::new("raw.json"));
+
+        assert!(Cli::try_parse_from(["mistral_ocr"]).is_err());
+
+        let cli = Cli::try_parse_from(["mistral_ocr", "a.pdf", "--keep-uploads"]).unwrap();
+        assert!(cli.run.upload_mode().is_err());
+        let cli = Cli::try_parse_from([
+            "mistral_ocr",
+            "a.pdf",
+            "--upload",
+            "files",
+            "--keep-uploads",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.run.upload_mode(),
+            Ok(UploadMode::Files {
+                delete_after: false
+            })
+        );
     }
 }
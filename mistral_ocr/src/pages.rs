@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{OcrError, OcrPage};
+
+/// Highest page number a selection may name. Far beyond what the API accepts in one
+/// document, but keeps a range such as `1-4000000000` from expanding without bound.
+const MAX_PAGE_NUMBER: u32 = 100_000;
+
+/// A set of pages to OCR, parsed from a 1-based list such as `1-5,10`.
+///
+/// Stored as sorted, de-duplicated zero-based indices, which is what the API expects.
+///
+/// ```
+/// use mistral_ocr::PageSelection;
+///
+/// let pages: PageSelection = "1-3,10".parse().unwrap();
+/// assert_eq!(pages.indices(), &[0, 1, 2, 9]);
+/// assert_eq!(pages.to_string(), "1-3,10");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageSelection(Vec<u32>);
+
+impl PageSelection {
+    /// Selection from zero-based page indices.
+    pub fn from_indices(indices: impl IntoIterator<Item = u32>) -> Self {
+        let set: BTreeSet<u32> = indices.into_iter().collect();
+        Self(set.into_iter().collect())
+    }
+
+    /// Zero-based page indices, ascending.
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    pub fn contains(&self, index: u32) -> bool {
+        self.0.binary_search(&index).is_ok()
+    }
+
+    /// Make page indices refer to the original document.
+    ///
+    /// If the API numbered the returned pages from zero instead of reporting their
+    /// original indices, map them back positionally onto the selection.
+    pub(crate) fn restore_indices(&self, pages: &mut [OcrPage]) {
+        if pages.iter().all(|p| self.contains(p.index)) || pages.len() != self.0.len() {
+            return;
+        }
+        for (page, &index) in pages.iter_mut().zip(&self.0) {
+            page.index = index;
+        }
+    }
+}
+
+impl FromStr for PageSelection {
+    type Err = OcrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| OcrError::InvalidPages(format!("{s:?}: {reason}"));
+        let parse_page = |p: &str| -> Result<u32, OcrError> {
+            match p.trim().parse::<u32>() {
+                Ok(0) => Err(invalid("page numbers start at 1")),
+                Ok(n) if n > MAX_PAGE_NUMBER => {
+                    Err(invalid(&format!("page numbers go up to {MAX_PAGE_NUMBER}")))
+                }
+                Ok(n) => Ok(n - 1),
+                Err(_) => Err(invalid(&format!("{:?} is not a page number", p.trim()))),
+            }
+        };
+
+        let mut indices = BTreeSet::new();
+        for part in s.split(',').filter(|p| !p.trim().is_empty()) {
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse_page(start)?, parse_page(end)?);
+                    if start > end {
+                        return Err(invalid(&format!("range {} is reversed", part.trim())));
+                    }
+                    indices.extend(start..=end);
+                }
+                None => {
+                    indices.insert(parse_page(part)?);
+                }
+            }
+        }
+        if indices.is_empty() {
+            return Err(invalid("no pages selected"));
+        }
+        Ok(Self(indices.into_iter().collect()))
+    }
+}
+
+/// Serialized as the same 1-based string syntax, e.g. `"1-5,10"`.
+impl Serialize for PageSelection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PageSelection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for PageSelection {
+    /// Formats as 1-based ranges, the same syntax [`FromStr`] accepts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut i = 0;
+        while i < self.0.len() {
+            let start = self.0[i];
+            let mut end = start;
+            while i + 1 < self.0.len() && self.0[i + 1] == end + 1 {
+                i += 1;
+                end += 1;
+            }
+            if start != self.0[0] {
+                f.write_str(",")?;
+            }
+            if start == end {
+                write!(f, "{}", start + 1)?;
+            } else {
+                write!(f, "{}-{}", start + 1, end + 1)?;
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(index: u32) -> OcrPage {
+        OcrPage {
+            index,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_ranges_and_single_pages() {
+        let pages: PageSelection = " 10, 1-3 ,2".parse().unwrap();
+        assert_eq!(pages.indices(), &[0, 1, 2, 9]);
+        assert_eq!(pages.to_string(), "1-3,10");
+
+        let pages: PageSelection = format!("1-{MAX_PAGE_NUMBER}").parse().unwrap();
+        assert_eq!(pages.indices().len(), MAX_PAGE_NUMBER as usize);
+    }
+
+    #[test]
+    fn rejects_invalid_selections() {
+        for input in [
+            "",
+            "0",
+            "3-1",
+            "a-b",
+            "1,,x",
+            "-2",
+            "1-4000000000",
+            "100001",
+        ] {
+            assert!(
+                matches!(
+                    input.parse::<PageSelection>(),
+                    Err(OcrError::InvalidPages(_))
+                ),
+                "{input:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn restores_original_indices_when_renumbered() {
+        let selection: PageSelection = "5,9".parse().unwrap();
+
+        let mut renumbered = vec![page(0), page(1)];
+        selection.restore_indices(&mut renumbered);
+        assert_eq!(renumbered[0].index, 4);
+        assert_eq!(renumbered[1].index, 8);
+
+        let mut original = vec![page(4), page(8)];
+        selection.restore_indices(&mut original);
+        assert_eq!(original[0].index, 4);
+        assert_eq!(original[1].index, 8);
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Spaces requests evenly to stay under a requests-per-minute budget.
+///
+/// Shared by all clones of a client, so concurrent workers draw from one budget.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn per_minute(requests: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / requests.max(1),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Reserve the next free slot and return how long to wait for it.
+    pub(crate) fn reserve(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_spaced_by_the_interval() {
+        let limiter = RateLimiter::per_minute(60);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        let second = limiter.reserve();
+        let third = limiter.reserve();
+        assert!(second > Duration::from_millis(900) && second <= Duration::from_secs(1));
+        assert!(third > Duration::from_millis(1900) && third <= Duration::from_secs(2));
+    }
+}
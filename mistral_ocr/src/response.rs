@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::error::IoContext;
+use crate::{OcrError, Result};
+
+/// OCR response as returned by the API, with the parts of a split document merged.
+///
+/// This is also the schema of [`OutputFormat::Json`](crate::OutputFormat::Json) output.
+/// Fields not modelled here are kept in `extra`, so a response saved with
+/// [`save`](Self::save) is a faithful archive of the API output that can be rendered
+/// again later with [`OcrDocument::from_raw`](crate::OcrDocument::from_raw).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OcrResponse {
+    pub pages: Vec<OcrPage>,
+    /// Model that produced the response, as reported by the API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Summed over all requests when a document was split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_info: Option<UsageInfo>,
+    /// JSON text filling in [`OcrOptions::document_annotation_format`](crate::OcrOptions::document_annotation_format).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_annotation: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OcrPage {
+    /// Zero-based page index within the source document.
+    pub index: u32,
+    /// Page content; images are referenced by their id, e.g. `![img-0.jpeg](img-0.jpeg)`.
+    pub markdown: String,
+    #[serde(default)]
+    pub images: Vec<OcrImage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<PageDimensions>,
+    /// Running header the API moved out of `markdown`, when asked to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    /// Running footer the API moved out of `markdown`, when asked to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An image cut out of a page, with its bounding box in page pixels.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OcrImage {
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_left_x: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_left_y: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_right_x: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_right_y: Option<u32>,
+    /// Image data as a `data:` URI or plain base64; only present when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_base64: Option<String>,
+    /// JSON text filling in [`OcrOptions::bbox_annotation_format`](crate::OcrOptions::bbox_annotation_format).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_annotation: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Size of a rendered page in pixels, and the resolution it was rendered at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageDimensions {
+    pub dpi: u32,
+    pub height: u32,
+    pub width: u32,
+}
+
+/// Rectangle in page pixels, see [`PageDimensions`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub top_left_x: u32,
+    pub top_left_y: u32,
+    pub bottom_right_x: u32,
+    pub bottom_right_y: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageInfo {
+    pub pages_processed: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_size_bytes: Option<u64>,
+}
+
+impl OcrResponse {
+    /// Read a response saved with [`save`](Self::save), or a response body saved from
+    /// the API directly.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json =
+            fs::read_to_string(path).io_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json)
+            .map_err(|e| OcrError::decode(format!("Invalid OCR response {}", path.display()), e))
+    }
+
+    /// Write the response as JSON, creating parent directories as needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .io_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| OcrError::decode("Failed to serialize OCR response", e))?;
+        fs::write(path, json).io_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+impl OcrImage {
+    /// Bounding box of the image on its page, when the API reported all four corners.
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        Some(BoundingBox {
+            top_left_x: self.top_left_x?,
+            top_left_y: self.top_left_y?,
+            bottom_right_x: self.bottom_right_x?,
+            bottom_right_y: self.bottom_right_y?,
+        })
+    }
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> u32 {
+        self.bottom_right_x.saturating_sub(self.top_left_x)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.bottom_right_y.saturating_sub(self.top_left_y)
+    }
+}
+
+impl UsageInfo {
+    pub(crate) fn add(&mut self, other: &UsageInfo) {
+        self.pages_processed += other.pages_processed;
+        self.doc_size_bytes = match (self.doc_size_bytes, other.doc_size_bytes) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_response_round_trips_with_unknown_fields() {
+        let body = serde_json::json!({
+            "model": "mistral-ocr-2505",
+            "usage_info": {"pages_processed": 1, "doc_size_bytes": 1234},
+            "document_annotation": "{\"total\": 12}",
+            "pages_layout": "single",
+            "pages": [{
+                "index": 0,
+                "markdown": "![img-0.jpeg](img-0.jpeg)",
+                "dimensions": {"dpi": 200, "height": 2200, "width": 1700},
+                "images": [{
+                    "id": "img-0.jpeg",
+                    "top_left_x": 10, "top_left_y": 20,
+                    "bottom_right_x": 110, "bottom_right_y": 220,
+                    "image_base64": null,
+                    "image_annotation": null,
+                    "caption": "Figure 1",
+                }],
+            }],
+        });
+        let response: OcrResponse = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(response.model.as_deref(), Some("mistral-ocr-2505"));
+        assert_eq!(response.usage_info.unwrap().doc_size_bytes, Some(1234));
+        let page = &response.pages[0];
+        assert_eq!(page.dimensions.unwrap().width, 1700);
+        let bbox = page.images[0].bbox().unwrap();
+        assert_eq!((bbox.width(), bbox.height()), (100, 200));
+        assert!(page.images[0].extra.contains_key("caption"));
+        assert!(response.extra.contains_key("pages_layout"));
+
+        let mut expected = body;
+        expected["pages"][0]["images"][0]
+            .as_object_mut()
+            .unwrap()
+            .retain(|key, value| !(key.starts_with("image_") && value.is_null()));
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+}
@@ -0,0 +1,272 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{OcrClient, OcrDocument, OcrError, OcrInput, OcrOptions, Result};
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A document to OCR and the path to write its markdown to.
+#[derive(Clone, Debug)]
+pub struct BatchJob<'a> {
+    pub input: OcrInput<'a>,
+    pub output: PathBuf,
+    /// Where to also save the raw API response, see [`OcrResponse::save`](crate::OcrResponse::save).
+    pub raw_output: Option<PathBuf>,
+}
+
+impl<'a> BatchJob<'a> {
+    pub fn new(input: OcrInput<'a>, output: impl Into<PathBuf>) -> Self {
+        Self {
+            input,
+            output: output.into(),
+            raw_output: None,
+        }
+    }
+
+    /// Also save the raw API response to `path`.
+    pub fn with_raw_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.raw_output = Some(path.into());
+        self
+    }
+}
+
+/// Reported once per document as it finishes, in completion order.
+#[derive(Debug)]
+pub struct BatchProgress<'a> {
+    /// Position of the document in the input list.
+    pub index: usize,
+    /// Documents finished so far, including this one.
+    pub completed: usize,
+    pub total: usize,
+    pub name: Option<&'a str>,
+    /// `None` if the document succeeded.
+    pub error: Option<&'a OcrError>,
+}
+
+type ProgressFn = dyn Fn(&BatchProgress) + Send + Sync;
+
+/// Processes many documents concurrently on a fixed number of worker threads, sharing
+/// one [`OcrClient`] and its connection pool.
+///
+/// Combine with [`OcrClientBuilder::requests_per_minute`](crate::OcrClientBuilder::requests_per_minute)
+/// to keep a large batch under the account's rate limit.
+///
+/// ```no_run
+/// use mistral_ocr::{BatchJob, BatchRunner, OcrClient, OcrInput, OcrOptions};
+///
+/// let client = OcrClient::builder("key").requests_per_minute(60).build()?;
+/// let jobs: Vec<_> = ["a.pdf", "b.pdf"]
+///     .into_iter()
+///     .map(|p| BatchJob::new(OcrInput::from_path(p), format!("{p}.md")))
+///     .collect();
+/// let results = BatchRunner::new(client)
+///     .concurrency(8)
+///     .on_progress(|p| println!("{}/{} done", p.completed, p.total))
+///     .run(&jobs, &OcrOptions::default());
+/// # Ok::<(), mistral_ocr::OcrError>(())
+/// ```
+pub struct BatchRunner {
+    client: OcrClient,
+    concurrency: usize,
+    on_progress: Option<Box<ProgressFn>>,
+}
+
+impl BatchRunner {
+    pub fn new(client: OcrClient) -> Self {
+        Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            on_progress: None,
+        }
+    }
+
+    /// Number of documents processed at the same time. Defaults to 4.
+    pub fn concurrency(mut self, workers: usize) -> Self {
+        self.concurrency = workers.max(1);
+        self
+    }
+
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&BatchProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// OCR every job and write its output. Results are in job order.
+    pub fn run(&self, jobs: &[BatchJob], options: &OcrOptions) -> Vec<Result<()>> {
+        self.for_each(
+            jobs.len(),
+            |i| jobs[i].input.file_name(),
+            |i| {
+                let job = &jobs[i];
+                self.client.write_outputs(
+                    &job.input,
+                    options,
+                    &job.output,
+                    job.raw_output.as_deref(),
+                )
+            },
+        )
+    }
+
+    /// OCR every input and return the documents, in input order.
+    pub fn process_all(
+        &self,
+        inputs: &[OcrInput],
+        options: &OcrOptions,
+    ) -> Vec<Result<OcrDocument>> {
+        self.for_each(
+            inputs.len(),
+            |i| inputs[i].file_name(),
+            |i| self.client.process_input(&inputs[i], options),
+        )
+    }
+
+    fn for_each<'a, T: Send>(
+        &self,
+        total: usize,
+        name: impl Fn(usize) -> Option<&'a str> + Sync,
+        work: impl Fn(usize) -> Result<T> + Sync,
+    ) -> Vec<Result<T>> {
+        let next = AtomicUsize::new(0);
+        let completed = AtomicUsize::new(0);
+        let slots: Vec<Option<Result<T>>> = (0..total).map(|_| None).collect();
+        let results = Mutex::new(slots);
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(total) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= total {
+                            break;
+                        }
+                        let result = work(index);
+                        let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                        if let Some(callback) = &self.on_progress {
+                            callback(&BatchProgress {
+                                index,
+                                completed: done,
+                                total,
+                                name: name(index),
+                                error: result.as_ref().err(),
+                            });
+                        }
+                        let mut results = results.lock().unwrap_or_else(|e| e.into_inner());
+                        results[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .map(|r| r.expect("every index is processed by a worker"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RetryPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn documents_are_processed_concurrently_in_input_order() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"document": {"document_name": "bad.pdf"}}),
+            ))
+            .with_status(400)
+            .with_body("bad document")
+            .create();
+        let ok = server
+            .mock("POST", "/ocr")
+            .with_body(
+                serde_json::json!({"pages": [{"index": 0, "markdown": "ok", "images": []}]})
+                    .to_string(),
+            )
+            .expect(5)
+            .create();
+
+        let client = OcrClient::builder("key")
+            .base_url(server.url())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let names = ["0.pdf", "1.pdf", "bad.pdf", "3.pdf", "4.pdf", "5.pdf"];
+        let inputs: Vec<_> = names
+            .iter()
+            .map(|n| OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name(*n))
+            .collect();
+
+        let progress = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let seen = progress.clone();
+        let results = BatchRunner::new(client)
+            .concurrency(3)
+            .on_progress(move |p| {
+                seen.lock().unwrap().push((
+                    p.completed,
+                    p.total,
+                    p.name.unwrap().to_string(),
+                    p.error.is_some(),
+                ));
+            })
+            .process_all(&inputs, &OcrOptions::default());
+
+        ok.assert();
+        assert_eq!(results.len(), 6);
+        assert!(matches!(results[2], Err(OcrError::Api { status: 400, .. })));
+        assert!(
+            results
+                .iter()
+                .enumerate()
+                .all(|(i, r)| (i == 2) == r.is_err())
+        );
+
+        let mut progress = progress.lock().unwrap().clone();
+        let mut completed: Vec<_> = progress.iter().map(|p| p.0).collect();
+        completed.sort();
+        assert_eq!(completed, [1, 2, 3, 4, 5, 6]);
+        progress.sort_by(|a, b| a.2.cmp(&b.2));
+        assert!(
+            progress
+                .iter()
+                .all(|p| p.1 == 6 && p.3 == (p.2 == "bad.pdf"))
+        );
+    }
+
+    #[test]
+    fn rate_limit_spaces_requests() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/ocr")
+            .with_body(r#"{"pages": []}"#)
+            .expect(3)
+            .create();
+        let client = OcrClient::builder("key")
+            .base_url(server.url())
+            .requests_per_minute(600)
+            .build()
+            .unwrap();
+        let inputs: Vec<_> = (0..3)
+            .map(|_| OcrInput::from_bytes(&b"%PDF-1.4"[..]).with_file_name("a.pdf"))
+            .collect();
+
+        let start = std::time::Instant::now();
+        let results = BatchRunner::new(client)
+            .concurrency(3)
+            .process_all(&inputs, &OcrOptions::default());
+        assert!(results.iter().all(Result::is_ok));
+        // 600/min is one request per 100 ms: the third waits for two intervals.
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
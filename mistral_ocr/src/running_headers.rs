@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::OcrDocument;
+
+/// Lines at the top and at the bottom of each page that may be running headers or footers.
+const EDGE_LINES: usize = 3;
+
+/// Fewer pages give too little evidence to tell a running header from content.
+const MIN_PAGES: usize = 3;
+
+/// Longer lines are content, however often they repeat.
+const MAX_LINE_CHARS: usize = 80;
+
+/// Words that may accompany a page number, as in `Page 3 of 12`.
+const PAGE_NUMBER_WORDS: &[&str] = &["page", "pages", "p", "pg", "of"];
+
+impl OcrDocument {
+    /// Remove running headers, footers and page numbers from the page markdown, and return
+    /// the distinct lines removed, in the order they were first seen.
+    ///
+    /// A line counts as running when it appears among the first or last few lines of more
+    /// than half of the pages of a document of at least three. Lines are compared ignoring
+    /// case and emphasis markers; page numbers on the very first or last line of a page
+    /// are also compared ignoring their value, so `Page 3 of 12` matches `Page 4 of 12`.
+    /// Headings, long lines, table rows and images are never removed. Headers and footers
+    /// that the API already extracted are returned as well.
+    pub fn strip_running_headers(&mut self) -> Vec<String> {
+        let mut removed = Vec::new();
+        for page in &self.pages {
+            for line in [&page.header, &page.footer].into_iter().flatten() {
+                let line = line.trim();
+                if !line.is_empty() && !removed.iter().any(|r| r == line) {
+                    removed.push(line.to_string());
+                }
+            }
+        }
+        if self.pages.len() < MIN_PAGES {
+            return removed;
+        }
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for page in &self.pages {
+            let lines: Vec<&str> = page.markdown.lines().collect();
+            let keys: HashSet<String> = edge_keys(&lines).into_iter().map(|(_, key)| key).collect();
+            for key in keys {
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+        let pages = self.pages.len();
+        let running: HashSet<String> = counts
+            .into_iter()
+            .filter(|&(_, count)| count * 2 > pages)
+            .map(|(key, _)| key)
+            .collect();
+        if running.is_empty() {
+            return removed;
+        }
+
+        // Lines differing only in their numbers are reported once.
+        let mut reported = HashSet::new();
+        for page in &mut self.pages {
+            let lines: Vec<&str> = page.markdown.lines().collect();
+            let mut strip = HashSet::new();
+            for (i, key) in edge_keys(&lines) {
+                if running.contains(&key) && strip.insert(i) && reported.insert(key) {
+                    removed.push(lines[i].trim().to_string());
+                }
+            }
+            if strip.is_empty() {
+                continue;
+            }
+            let kept: Vec<&str> = lines
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !strip.contains(i))
+                .map(|(_, line)| *line)
+                .collect();
+            page.markdown = kept.join("\n").trim().to_string();
+        }
+        removed
+    }
+}
+
+/// Comparison keys of the first and last [`EDGE_LINES`] non-blank lines, with their
+/// indices. The outermost lines get a second key with numbers masked.
+fn edge_keys(lines: &[&str]) -> Vec<(usize, String)> {
+    let content: Vec<usize> = (0..lines.len())
+        .filter(|&i| !lines[i].trim().is_empty())
+        .collect();
+    let mut edges: Vec<usize> = content.iter().take(EDGE_LINES).copied().collect();
+    for &i in content.iter().rev().take(EDGE_LINES) {
+        if !edges.contains(&i) {
+            edges.push(i);
+        }
+    }
+    let outermost = [content.first(), content.last()];
+    let mut keys = Vec::new();
+    for i in edges {
+        keys.extend(running_key(lines[i], false).map(|key| (i, key)));
+        if outermost.contains(&Some(&i)) {
+            keys.extend(running_key(lines[i], true).map(|key| (i, key)));
+        }
+    }
+    keys
+}
+
+/// What a line is compared by, or `None` for lines that are never removed. With
+/// `mask_numbers`, every number reads as `#`, and only page numbers have a key.
+fn running_key(line: &str, mask_numbers: bool) -> Option<String> {
+    let line = line.trim();
+    if ["|", "![", "#"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || line.chars().count() > MAX_LINE_CHARS
+    {
+        return None;
+    }
+    let text = line.trim_matches(|c: char| c == '*' || c == '_' || c.is_whitespace());
+    if mask_numbers && !is_page_number(text) {
+        return None;
+    }
+    let mut key = String::with_capacity(text.len());
+    for c in text.chars() {
+        if mask_numbers && c.is_ascii_digit() {
+            if !key.ends_with('#') {
+                key.push('#');
+            }
+        } else if c.is_whitespace() {
+            if !key.ends_with(' ') {
+                key.push(' ');
+            }
+        } else {
+            key.extend(c.to_lowercase());
+        }
+    }
+    (!key.is_empty()).then_some(key)
+}
+
+/// Whether `text` is a page number, possibly with words such as `Page 3 of 12`, rather
+/// than a numbered title such as `Chapter 3`.
+fn is_page_number(text: &str) -> bool {
+    text.contains(|c: char| c.is_ascii_digit())
+        && text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .all(|word| PAGE_NUMBER_WORDS.contains(&word.to_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DocumentPage;
+
+    fn document_of(pages: &[String]) -> OcrDocument {
+        OcrDocument {
+            source_name: None,
+            source_sha256: None,
+            model: "m".to_string(),
+            usage_info: None,
+            document_annotation: None,
+            pages: pages
+                .iter()
+                .enumerate()
+                .map(|(i, md)| DocumentPage {
+                    index: i as u32,
+                    markdown: md.clone(),
+                    images: vec![],
+                    dimensions: None,
+                    header: None,
+                    footer: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn lines_repeated_at_page_edges_are_removed() {
+        let pages: Vec<String> = (1..=4)
+            .map(|n| {
+                format!(
+                    "**ACME Corp — Annual Report 2024**\n\n# Section {n}\n\nBody text {n}.\n\n\
+                     | Item | Value |\n|---|---|\n| a | {n} |\n\nPage {n} of 4"
+                )
+            })
+            .collect();
+        let mut document = document_of(&pages);
+        document.pages[3].markdown = "Closing remarks.\n\nPage 4 of 4".to_string();
+
+        let removed = document.strip_running_headers();
+        assert_eq!(
+            removed,
+            ["**ACME Corp — Annual Report 2024**", "Page 1 of 4"]
+        );
+        assert_eq!(
+            document.pages[0].markdown,
+            "# Section 1\n\nBody text 1.\n\n| Item | Value |\n|---|---|\n| a | 1 |"
+        );
+        assert_eq!(document.pages[3].markdown, "Closing remarks.");
+    }
+
+    #[test]
+    fn numbered_headings_and_titles_on_short_pages_are_kept() {
+        let pages: Vec<String> = (1..=4)
+            .map(|n| format!("# Chapter {n}\n\nSlide {n}\n\n{n}"))
+            .collect();
+        let mut document = document_of(&pages);
+        assert_eq!(document.strip_running_headers(), ["1"]);
+        assert_eq!(document.pages[1].markdown, "# Chapter 2\n\nSlide 2");
+
+        let pages: Vec<String> = (1..=4)
+            .map(|n| format!("## Introduction\n\nPart {n}"))
+            .collect();
+        let mut document = document_of(&pages);
+        assert!(document.strip_running_headers().is_empty());
+        assert_eq!(document.pages[3].markdown, pages[3]);
+
+        let long = "A sentence that repeats at the end of every page, but is far too long to \
+                    be a running footer.";
+        let pages: Vec<String> = (1..=4).map(|n| format!("Body {n}\n\n{long}")).collect();
+        let mut document = document_of(&pages);
+        assert!(document.strip_running_headers().is_empty());
+    }
+
+    #[test]
+    fn short_documents_and_api_extractions() {
+        let mut document = document_of(&["Header\n\nOne".to_string(), "Header\n\nTwo".to_string()]);
+        document.pages[1].footer = Some("Confidential".to_string());
+        assert_eq!(document.strip_running_headers(), ["Confidential"]);
+        assert_eq!(document.pages[0].markdown, "Header\n\nOne");
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+use tracing::info;
+
+use crate::{OcrError, OcrPage, Result};
+
+/// A contiguous range of pages cut out of a larger PDF.
+pub(crate) struct PdfChunk {
+    pub(crate) data: Vec<u8>,
+    /// Zero-based index of the chunk's first page in the original document.
+    pub(crate) first_page: u32,
+    pub(crate) page_count: u32,
+}
+
+/// Split a PDF into page-range chunks that are each at most `limit` bytes.
+///
+/// Chunk sizes start from a proportional estimate and are halved until they fit; a
+/// single page that is still over the limit cannot be split further and is an error.
+pub(crate) fn split_pdf(data: &[u8], limit: u64) -> Result<Vec<PdfChunk>> {
+    let doc = lopdf::Document::load_mem(data)
+        .map_err(|e| OcrError::decode("Failed to parse PDF for splitting", e))?;
+    let total = doc.get_pages().len() as u32;
+
+    // Aim below the limit so most estimates fit on the first try.
+    let estimate = (u64::from(total) * limit * 9 / 10 / (data.len() as u64).max(1)) as u32;
+    let mut per_chunk = estimate.clamp(1, total.max(1));
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < total {
+        let mut count = per_chunk.min(total - start);
+        let chunk = loop {
+            let bytes = extract_pages(&doc, total, start, count)?;
+            if bytes.len() as u64 <= limit {
+                break bytes;
+            }
+            if count == 1 {
+                return Err(OcrError::FileTooLarge {
+                    size: bytes.len() as u64,
+                    limit,
+                });
+            }
+            count /= 2;
+            per_chunk = count;
+        };
+        info!(
+            "Split pages {}-{} into a {:.1} MB chunk",
+            start + 1,
+            start + count,
+            chunk.len() as f64 / (1024.0 * 1024.0)
+        );
+        chunks.push(PdfChunk {
+            data: chunk,
+            first_page: start,
+            page_count: count,
+        });
+        start += count;
+    }
+    Ok(chunks)
+}
+
+/// Serialize pages `start..start + count` (zero-based) of `doc` as a standalone PDF.
+fn extract_pages(doc: &lopdf::Document, total: u32, start: u32, count: u32) -> Result<Vec<u8>> {
+    let mut doc = doc.clone();
+    let delete: Vec<u32> = (1..=total)
+        .filter(|n| *n <= start || *n > start + count)
+        .collect();
+    doc.delete_pages(&delete);
+    doc.prune_objects();
+    let mut out = Vec::new();
+    doc.save_to(&mut out)
+        .map_err(|e| OcrError::decode("Failed to write PDF chunk", e))?;
+    Ok(out)
+}
+
+/// Rename images of a later chunk so their ids don't collide with earlier chunks.
+///
+/// The API numbers images per request (`img-0.jpeg`, `img-1.jpeg`, ...), so every chunk
+/// starts again from zero. `img-N` ids are shifted past the highest number already used;
+/// other ids only get a prefix if they collide. Markdown links are rewritten to match.
+pub(crate) fn renumber_images(pages: &mut [OcrPage], used: &mut HashSet<String>, next: &mut u32) {
+    let offset = *next;
+    for page in pages.iter_mut() {
+        let mut renames = Vec::new();
+        for img in &mut page.images {
+            let Some(id) = &img.id else { continue };
+            let new_id = match parse_numbered_id(id) {
+                Some((n, ext)) => {
+                    *next = (*next).max(offset + n + 1);
+                    format!("img-{}{ext}", offset + n)
+                }
+                None if used.contains(id) => format!("p{}-{id}", page.index + 1),
+                None => id.clone(),
+            };
+            used.insert(new_id.clone());
+            if new_id != *id {
+                renames.push((id.clone(), new_id.clone()));
+                img.id = Some(new_id);
+            }
+        }
+        // Highest numbers first, so a rename never produces an id that is still
+        // waiting to be renamed itself.
+        renames.sort_by_key(|(old, _)| std::cmp::Reverse(parse_numbered_id(old).map(|(n, _)| n)));
+        for (old, new) in renames {
+            page.markdown = page
+                .markdown
+                .replace(&format!("]({old})"), &format!("]({new})"));
+        }
+    }
+}
+
+/// Split `img-12.jpeg` into `(12, ".jpeg")`.
+fn parse_numbered_id(id: &str) -> Option<(u32, &str)> {
+    let rest = id.strip_prefix("img-")?;
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let n = rest[..digits].parse().ok()?;
+    Some((n, &rest[digits..]))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::OcrImage;
+    use lopdf::{Object, Stream, dictionary};
+
+    /// A PDF with `pages` pages, each carrying `bytes_per_page` of incompressible content.
+    pub(crate) fn sample_pdf(pages: u32, bytes_per_page: usize) -> Vec<u8> {
+        let mut doc = lopdf::Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let mut kids = Vec::new();
+        let mut seed = 0x2545_f491_u32;
+        for n in 0..pages {
+            let filler: Vec<u8> = (0..bytes_per_page)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    seed as u8
+                })
+                .collect();
+            let mut content =
+                format!("BT /F1 12 Tf 72 720 Td (Page {}) Tj ET\n%", n + 1).into_bytes();
+            content.extend(filler);
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Contents" => content_id,
+            });
+            kids.push(Object::Reference(page_id));
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => pages as i64,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        let mut out = Vec::new();
+        doc.save_to(&mut out).unwrap();
+        out
+    }
+
+    fn page_count(data: &[u8]) -> usize {
+        lopdf::Document::load_mem(data).unwrap().get_pages().len()
+    }
+
+    #[test]
+    fn splits_into_chunks_under_the_limit() {
+        let pdf = sample_pdf(10, 10_000);
+        let limit = 35_000;
+        let chunks = split_pdf(&pdf, limit).unwrap();
+
+        assert!(chunks.len() > 1);
+        let mut expected_start = 0;
+        for chunk in &chunks {
+            assert!(chunk.data.len() as u64 <= limit);
+            assert_eq!(chunk.first_page, expected_start);
+            assert_eq!(page_count(&chunk.data), chunk.page_count as usize);
+            let doc = lopdf::Document::load_mem(&chunk.data).unwrap();
+            let first = *doc.get_pages().values().next().unwrap();
+            let content = doc.get_page_content(first);
+            let marker = format!("(Page {})", chunk.first_page + 1);
+            assert!(
+                content
+                    .windows(marker.len())
+                    .any(|w| w == marker.as_bytes())
+            );
+            expected_start += chunk.page_count;
+        }
+        assert_eq!(expected_start, 10);
+    }
+
+    #[test]
+    fn single_page_over_the_limit_is_an_error() {
+        let pdf = sample_pdf(2, 50_000);
+        assert!(matches!(
+            split_pdf(&pdf, 20_000),
+            Err(OcrError::FileTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn renumbers_images_without_chaining_renames() {
+        let image = |id: &str| OcrImage {
+            id: Some(id.to_string()),
+            ..Default::default()
+        };
+        let mut used = HashSet::from(["img-0.jpeg".to_string(), "logo.png".to_string()]);
+        let mut next = 1;
+        let mut pages = vec![OcrPage {
+            index: 5,
+            markdown: "![a](img-0.jpeg) ![b](img-1.jpeg) ![c](logo.png)".to_string(),
+            images: vec![image("img-0.jpeg"), image("img-1.jpeg"), image("logo.png")],
+            ..Default::default()
+        }];
+        renumber_images(&mut pages, &mut used, &mut next);
+
+        assert_eq!(
+            pages[0].markdown,
+            "![a](img-1.jpeg) ![b](img-2.jpeg) ![c](p6-logo.png)"
+        );
+        let ids: Vec<_> = pages[0]
+            .images
+            .iter()
+            .map(|i| i.id.as_deref().unwrap())
+            .collect();
+        assert_eq!(ids, ["img-1.jpeg", "img-2.jpeg", "p6-logo.png"]);
+        assert_eq!(next, 3);
+    }
+}
@@ -0,0 +1,340 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::IoContext;
+use crate::{OcrDocument, Result, create_parent_dir, output_stem};
+
+/// File format for tables extracted with [`write_tables`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    /// One CSV file per table.
+    Csv,
+    /// One workbook with a sheet per table.
+    Xlsx,
+}
+
+/// A markdown table from the pages of an [`OcrDocument`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    /// Zero-based index of the page the table starts on.
+    pub page: u32,
+    /// Zero-based index of the page the table ends on. Later than `page` when the table
+    /// continued on the following pages, repeating its header row.
+    pub last_page: u32,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Page numbers the table is on, e.g. `page 3` or `pages 3-4`.
+    fn pages_label(&self) -> String {
+        if self.last_page == self.page {
+            format!("page {}", self.page + 1)
+        } else {
+            format!("pages {}-{}", self.page + 1, self.last_page + 1)
+        }
+    }
+}
+
+impl OcrDocument {
+    /// Tables in the page markdown, in reading order, with cell text stripped of markdown
+    /// syntax.
+    ///
+    /// A table that is the last table on one page and the first on the next with the same
+    /// header row is taken to continue across the page break and is returned once.
+    pub fn tables(&self) -> Vec<Table> {
+        let mut tables: Vec<Table> = Vec::new();
+        for page in &self.pages {
+            for (i, table) in parse_tables(&page.markdown).into_iter().enumerate() {
+                if let Some(prev) = tables.last_mut()
+                    && i == 0
+                    && prev.last_page + 1 == page.index
+                    && prev.header == table.header
+                {
+                    prev.last_page = page.index;
+                    prev.rows.extend(table.rows);
+                    continue;
+                }
+                tables.push(Table {
+                    page: page.index,
+                    last_page: page.index,
+                    ..table
+                });
+            }
+        }
+        tables
+    }
+}
+
+/// Tables in one page of markdown, with `page` and `last_page` unset.
+fn parse_tables(markdown: &str) -> Vec<Table> {
+    let mut tables = Vec::new();
+    let mut table: Option<Table> = None;
+    let mut row: Vec<String> = Vec::new();
+    let mut cell: Option<String> = None;
+    let mut in_image = false;
+    for event in Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_MATH) {
+        if in_image {
+            in_image = !matches!(event, Event::End(TagEnd::Image));
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Table(_)) => {
+                table = Some(Table {
+                    page: 0,
+                    last_page: 0,
+                    header: Vec::new(),
+                    rows: Vec::new(),
+                });
+            }
+            Event::End(TagEnd::Table) => tables.extend(table.take()),
+            Event::Start(Tag::TableCell) => cell = Some(String::new()),
+            Event::End(TagEnd::TableCell) => row.extend(cell.take().map(|c| c.trim().to_string())),
+            Event::End(TagEnd::TableHead) => {
+                if let Some(table) = &mut table {
+                    table.header = std::mem::take(&mut row);
+                }
+            }
+            Event::End(TagEnd::TableRow) => {
+                if let Some(table) = &mut table {
+                    table.rows.push(std::mem::take(&mut row));
+                }
+            }
+            Event::Start(Tag::Image { .. }) => in_image = true,
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) | Event::DisplayMath(t) => {
+                if let Some(cell) = &mut cell {
+                    cell.push_str(&t);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak | Event::InlineHtml(_) => {
+                // OCR output breaks lines within cells with `<br>`.
+                if let Some(cell) = &mut cell {
+                    cell.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// Where [`write_tables`] writes for `output_path`: the `<stem>_tables` directory of CSV
+/// files, or the `<stem>.xlsx` workbook.
+pub fn tables_path(output_path: &Path, format: TableFormat) -> PathBuf {
+    match format {
+        TableFormat::Csv => output_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(format!("{}_tables", output_stem(output_path))),
+        TableFormat::Xlsx => output_path.with_extension("xlsx"),
+    }
+}
+
+/// Write the tables of `document` next to `output_path`, see [`tables_path`], and return
+/// how many there were. Nothing is written for a document without tables.
+///
+/// CSV files are named after the table number and the pages the table is on, e.g.
+/// `table-2-pages-3-4.csv`; workbook sheets likewise, e.g. `Table 2 (pages 3-4)`. The
+/// header row comes first, and in workbooks numeric cells are stored as numbers.
+pub fn write_tables(
+    output_path: &Path,
+    document: &OcrDocument,
+    format: TableFormat,
+) -> Result<usize> {
+    let tables = document.tables();
+    if tables.is_empty() {
+        return Ok(0);
+    }
+    let path = tables_path(output_path, format);
+    match format {
+        TableFormat::Csv => write_csv(&path, &tables)?,
+        TableFormat::Xlsx => write_xlsx(&path, &tables)?,
+    }
+    Ok(tables.len())
+}
+
+fn write_csv(dir: &Path, tables: &[Table]) -> Result<()> {
+    fs::create_dir_all(dir).io_context(|| format!("Failed to create {}", dir.display()))?;
+    for (i, table) in tables.iter().enumerate() {
+        let path = dir.join(format!(
+            "table-{}-{}.csv",
+            i + 1,
+            table.pages_label().replace(' ', "-")
+        ));
+        let write = || -> csv::Result<()> {
+            // Rows of a table may have fewer cells than the header.
+            let mut csv = csv::WriterBuilder::new().flexible(true).from_path(&path)?;
+            csv.write_record(&table.header)?;
+            for row in &table.rows {
+                csv.write_record(row)?;
+            }
+            csv.flush()?;
+            Ok(())
+        };
+        write()
+            .map_err(io::Error::from)
+            .io_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn write_xlsx(path: &Path, tables: &[Table]) -> Result<()> {
+    create_parent_dir(path)?;
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    for (i, table) in tables.iter().enumerate() {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(format!("Table {} ({})", i + 1, table.pages_label()))?;
+        for (col, text) in table.header.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, text, &bold)?;
+        }
+        for (row, cells) in table.rows.iter().enumerate() {
+            let row = row as u32 + 1;
+            for (col, text) in cells.iter().enumerate() {
+                match number(text) {
+                    Some(n) => sheet.write_number(row, col as u16, n)?,
+                    None => sheet.write_string(row, col as u16, text)?,
+                };
+            }
+        }
+    }
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// Most significant digits an `f64` holds exactly.
+const MAX_DIGITS: usize = 15;
+
+/// `text` as a number if it is a plain decimal such as `-1234.5`. Thousands separators
+/// are left alone, since they differ between locales, and so are numbers with leading
+/// zeros or more than [`MAX_DIGITS`] significant digits, such as ZIP codes, account
+/// numbers and long ids, which would lose digits.
+fn number(text: &str) -> Option<f64> {
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty()
+        || !all_digits(int)
+        || !all_digits(frac)
+        || (unsigned.contains('.') && frac.is_empty())
+        || (int.len() > 1 && int.starts_with('0'))
+    {
+        return None;
+    }
+    let significant = format!("{int}{frac}");
+    let significant = significant.trim_start_matches('0').trim_end_matches('0');
+    if significant.len() > MAX_DIGITS {
+        return None;
+    }
+    text.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DocumentPage;
+
+    fn document_of(pages: &[&str]) -> OcrDocument {
+        OcrDocument {
+            source_name: None,
+            source_sha256: None,
+            model: "m".to_string(),
+            usage_info: None,
+            document_annotation: None,
+            pages: pages
+                .iter()
+                .enumerate()
+                .map(|(i, md)| DocumentPage {
+                    index: i as u32,
+                    markdown: md.to_string(),
+                    images: vec![],
+                    dimensions: None,
+                    header: None,
+                    footer: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn tables_continuing_on_the_next_page_are_merged() {
+        let document = document_of(&[
+            "# Balance sheet\n\n| Item | 2024 |\n|---|---:|\n| **Cash** | 1200 |\n| Debt<br>long term | -300.5 |",
+            "| Item | 2024 |\n|---|---:|\n| Equity | 900 |\n\nNotes\n\n| Note | Text |\n|---|---|\n| 1 | `a` |",
+            "| Note | Text |\n|---|---|\n| 2 | b |",
+            "",
+            "| Note | Text |\n|---|---|\n| 3 | c |",
+        ]);
+        let tables = document.tables();
+        assert_eq!(tables.len(), 3);
+        assert_eq!((tables[0].page, tables[0].last_page), (0, 1));
+        assert_eq!(tables[0].header, ["Item", "2024"]);
+        assert_eq!(
+            tables[0].rows,
+            [
+                ["Cash", "1200"],
+                ["Debt long term", "-300.5"],
+                ["Equity", "900"]
+            ]
+        );
+        assert_eq!((tables[1].page, tables[1].last_page), (1, 2));
+        assert_eq!(tables[1].rows, [["1", "a"], ["2", "b"]]);
+        // Page 4 has no table, so the one on page 5 starts afresh.
+        assert_eq!((tables[2].page, tables[2].last_page), (4, 4));
+    }
+
+    #[test]
+    fn tables_are_written_as_csv_files_or_sheets() {
+        let document = document_of(&[
+            "| a | b |\n|---|---|\n| 1 | x, y |",
+            "| a | b |\n|---|---|\n| 2 | z |",
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("doc.md");
+
+        assert_eq!(write_tables(&out, &document, TableFormat::Csv).unwrap(), 1);
+        let csv = fs::read_to_string(dir.path().join("doc_tables/table-1-pages-1-2.csv")).unwrap();
+        assert_eq!(csv, "a,b\n1,\"x, y\"\n2,z\n");
+
+        assert_eq!(write_tables(&out, &document, TableFormat::Xlsx).unwrap(), 1);
+        let xlsx = fs::File::open(dir.path().join("doc.xlsx")).unwrap();
+        let mut archive = zip::ZipArchive::new(xlsx).unwrap();
+        let mut workbook = String::new();
+        io::Read::read_to_string(
+            &mut archive.by_name("xl/workbook.xml").unwrap(),
+            &mut workbook,
+        )
+        .unwrap();
+        assert!(workbook.contains("Table 1 (pages 1-2)"));
+
+        let empty = dir.path().join("empty.md");
+        assert_eq!(
+            write_tables(&empty, &document_of(&["No tables"]), TableFormat::Xlsx).unwrap(),
+            0
+        );
+        assert!(!dir.path().join("empty.xlsx").exists());
+    }
+
+    #[test]
+    fn only_plain_decimals_are_numbers() {
+        assert_eq!(number("-1234.5"), Some(-1234.5));
+        assert_eq!(number("1,234"), None);
+        assert_eq!(number("inf"), None);
+        assert_eq!(number("-"), None);
+        assert_eq!(number("0"), Some(0.0));
+        assert_eq!(number("-0.25"), Some(-0.25));
+        assert_eq!(number("00123"), None);
+        assert_eq!(number("-007"), None);
+        assert_eq!(number("1234567890123456789"), None);
+        assert_eq!(number("123456789012345"), Some(123456789012345.0));
+        assert_eq!(number("1000000000000000000"), Some(1e18));
+        for text in ["1-2", "--5", "1.2.3", "1.", ".5", "-"] {
+            assert_eq!(number(text), None, "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::fs;
+use std::path::Path;
+
+use crate::error::IoContext;
+use crate::html::is_line_break;
+use crate::{OcrDocument, OcrOptions, Result, create_parent_dir};
+
+/// Write an OCR result as plain text at `output_path`.
+///
+/// Images are dropped whatever `options.image_mode` says; see [`OcrDocument::text`].
+pub fn write_text(output_path: &Path, document: &OcrDocument, options: &OcrOptions) -> Result<()> {
+    create_parent_dir(output_path)?;
+    fs::write(output_path, document.text(options.page_headers))
+        .io_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+impl OcrDocument {
+    /// Text of all pages with markdown syntax removed: headings, emphasis, links and
+    /// table pipes are stripped and images dropped, keeping reading order and paragraph
+    /// breaks. Table cells are separated by tabs.
+    ///
+    /// With `page_headers`, pages of multi-page documents are separated by a
+    /// `--- Page N ---` line.
+    pub fn text(&self, page_headers: bool) -> String {
+        let multi_page = self.pages.len() > 1;
+        let mut output = String::new();
+        for page in &self.pages {
+            if multi_page && page_headers {
+                output.push_str(&format!("--- Page {} ---\n\n", page.index + 1));
+            }
+            let text = markdown_to_text(&page.markdown);
+            if !text.is_empty() {
+                output.push_str(&text);
+                output.push_str("\n\n");
+            }
+        }
+        output
+    }
+}
+
+/// Plain text of one page of markdown.
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_image = false;
+    let mut in_cell = false;
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let parser = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_MATH);
+    for event in parser {
+        if in_image {
+            in_image = !matches!(event, Event::End(TagEnd::Image));
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Image { .. }) => in_image = true,
+            Event::Start(Tag::List(start)) => lists.push(start),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                end_block(&mut text);
+            }
+            Event::Start(Tag::Item) => {
+                start_line(&mut text);
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        text.push_str(&format!("{n}. "));
+                        *n += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                in_cell = true;
+                if !text.ends_with('\n') && !text.is_empty() {
+                    text.push('\t');
+                }
+            }
+            Event::End(TagEnd::TableCell) => in_cell = false,
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => text.push('\n'),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::BlockQuote(_)
+                | TagEnd::CodeBlock
+                | TagEnd::Table,
+            ) if lists.is_empty() => end_block(&mut text),
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) | Event::DisplayMath(t) => {
+                text.push_str(&t);
+            }
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            // OCR output breaks lines with `<br>`; within a table cell a space keeps the
+            // row on one line, as in the workbook export.
+            Event::Html(html) | Event::InlineHtml(html) if is_line_break(&html) => {
+                text.push(if in_cell { ' ' } else { '\n' });
+            }
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+fn start_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// End a block with one blank line, however many blocks end at once.
+fn end_block(text: &mut String) {
+    let trimmed = text.trim_end_matches('\n').len();
+    text.truncate(trimmed);
+    if !text.is_empty() {
+        text.push_str("\n\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DocumentPage;
+
+    #[test]
+    fn markdown_syntax_is_stripped() {
+        let md = "# Report\n\nSome **bold** and _italic_ text with a [link](http://x).\n\
+                  Second line.\n\n![img-0.jpeg](img-0.jpeg)\n\n\
+                  - one\n- two\n\n\
+                  | Item | Cost |\n|------|-----:|\n| Tea | 3 |\n\n\
+                  Area $\\pi r^2$.";
+        assert_eq!(
+            markdown_to_text(md),
+            "Report\n\n\
+             Some bold and italic text with a link.\nSecond line.\n\n\
+             - one\n- two\n\n\
+             Item\tCost\nTea\t3\n\n\
+             Area \\pi r^2."
+        );
+    }
+
+    #[test]
+    fn line_break_tags_are_kept() {
+        assert_eq!(markdown_to_text("foo<br>bar<BR/>baz"), "foo\nbar\nbaz");
+        assert_eq!(
+            markdown_to_text("| Item |\n|---|\n| Debt<br>long term |"),
+            "Item\nDebt long term"
+        );
+    }
+
+    #[test]
+    fn pages_are_separated() {
+        let page = |index, markdown: &str| DocumentPage {
+            index,
+            markdown: markdown.to_string(),
+            images: vec![],
+            dimensions: None,
+            header: None,
+            footer: None,
+        };
+        let document = OcrDocument {
+            source_name: None,
+            source_sha256: None,
+            model: "m".to_string(),
+            usage_info: None,
+            document_annotation: None,
+            pages: vec![page(0, "## First"), page(1, "Second *page*")],
+        };
+        assert_eq!(
+            document.text(true),
+            "--- Page 1 ---\n\nFirst\n\n--- Page 2 ---\n\nSecond page\n\n"
+        );
+        assert_eq!(document.text(false), "First\n\nSecond page\n\n");
+    }
+}
@@ -1,5 +1,5 @@
 use eframe::egui;
-use mistral_ocr::{ImageMode, OcrOptions};
+use mistral_ocr::{ImageMode, OcrOptions, PageSelection};
 use std::path::PathBuf;
 use std::sync::{
     Arc, Mutex,
@@ -65,6 +65,10 @@ struct OcrApp {
     /// Output path tracks the input file name until the user edits it manually.
     output_auto: bool,
     page_headers: bool,
+    /// Page header template, e.g. `<!-- page {n} -->`; empty means `# Page N`.
+    page_separator: String,
+    /// Page selection as typed, e.g. `1-5,10`; empty means all pages.
+    pages: String,
     api_key: String,
     log: Arc<Mutex<String>>,
     running: Arc<AtomicBool>,
@@ -80,6 +84,8 @@ impl OcrApp {
             output_path: String::new(),
             output_auto: true,
             page_headers: true,
+            page_separator: String::new(),
+            pages: String::new(),
             api_key,
             log,
             running: Arc::new(AtomicBool::new(false)),
@@ -205,6 +211,27 @@ impl eframe::App for OcrApp {
                     ui.checkbox(&mut self.page_headers, "Insert `# Page N` between pages");
                     ui.label("");
                     ui.end_row();
+
+                    // Page header template
+                    ui.label("Page separator:");
+                    ui.add_enabled(
+                        self.page_headers,
+                        egui::TextEdit::singleline(&mut self.page_separator)
+                            .desired_width(400.0)
+                            .hint_text("# Page {n}  ({total} pages, {source} file name)"),
+                    );
+                    ui.label("");
+                    ui.end_row();
+
+                    // Page selection
+                    ui.label("Pages:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.pages)
+                            .desired_width(400.0)
+                            .hint_text("all pages, or e.g. 1-5,10"),
+                    );
+                    ui.label("");
+                    ui.end_row();
                 });
 
             ui.add_space(12.0);
@@ -250,9 +277,24 @@ impl OcrApp {
         self.running.store(true, Ordering::Relaxed);
 
         let input = PathBuf::from(&self.input_path);
+        let pages = if self.pages.trim().is_empty() {
+            None
+        } else {
+            match self.pages.parse::<PageSelection>() {
+                Ok(pages) => Some(pages),
+                Err(e) => {
+                    error!("{e}");
+                    self.running.store(false, Ordering::Relaxed);
+                    return;
+                }
+            }
+        };
         let options = OcrOptions {
             image_mode: self.image_mode,
             page_headers: self.page_headers,
+            page_separator: Some(self.page_separator.clone()).filter(|s| !s.trim().is_empty()),
+            pages,
+            ..Default::default()
         };
         let output = if self.output_path.is_empty() {
             input.with_extension("md")
@@ -263,7 +305,7 @@ impl OcrApp {
         let running = self.running.clone();
 
         std::thread::spawn(move || {
-            if let Err(e) = mistral_ocr::run_ocr(&input, options, &output, &api_key) {
+            if let Err(e) = mistral_ocr::run_ocr(&input, &options, &output, &api_key) {
                 error!("{e:#}");
             }
             running.store(false, Ordering::Relaxed);